@@ -0,0 +1,8 @@
+-- SQLite can't drop columns, so rebuild the table without it
+CREATE TABLE games_old (
+    id VARCHAR(12) NOT NULL PRIMARY KEY,
+    json VARCHAR NOT NULL
+);
+INSERT INTO games_old SELECT id, json FROM games;
+DROP TABLE games;
+ALTER TABLE games_old RENAME TO games
@@ -0,0 +1,2 @@
+-- Every save bumps the version, so stale writes can be detected
+ALTER TABLE games ADD COLUMN version INTEGER NOT NULL DEFAULT 0
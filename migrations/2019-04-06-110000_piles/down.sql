@@ -0,0 +1,15 @@
+-- Put the piles back into each game's json
+UPDATE games SET json = json_set(json, '$.piles', (
+    SELECT json_group_object(piles.name, json((
+        SELECT json_group_array(code) FROM (
+            SELECT code FROM pile_cards
+            WHERE pile_cards.game_id = piles.game_id AND pile_cards.pile = piles.name
+            ORDER BY position
+        )
+    )))
+    FROM piles
+    WHERE piles.game_id = games.id
+));
+
+DROP TABLE pile_cards;
+DROP TABLE piles
@@ -0,0 +1,28 @@
+-- Piles and their cards get their own rows, top of a pile is its highest position
+CREATE TABLE piles (
+    game_id VARCHAR(12) NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    PRIMARY KEY (game_id, name)
+);
+
+CREATE TABLE pile_cards (
+    game_id VARCHAR(12) NOT NULL,
+    pile VARCHAR NOT NULL,
+    position INTEGER NOT NULL,
+    code VARCHAR NOT NULL,
+    PRIMARY KEY (game_id, pile, position),
+    FOREIGN KEY (game_id, pile) REFERENCES piles(game_id, name) ON DELETE CASCADE
+);
+
+CREATE INDEX pile_cards_code ON pile_cards (code, pile);
+
+-- Move the piles of existing games out of their json
+INSERT INTO piles (game_id, name)
+SELECT games.id, p.key
+FROM games, json_each(games.json, '$.piles') AS p;
+
+INSERT INTO pile_cards (game_id, pile, position, code)
+SELECT games.id, p.key, c.key, c.value
+FROM games, json_each(games.json, '$.piles') AS p, json_each(p.value) AS c;
+
+UPDATE games SET json = json_remove(json, '$.piles')
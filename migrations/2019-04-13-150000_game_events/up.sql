@@ -0,0 +1,25 @@
+-- Append only log of everything that changed a game's piles, in seq order
+CREATE TABLE game_events (
+    game_id VARCHAR(12) NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    seq INTEGER NOT NULL,
+    kind VARCHAR NOT NULL,
+    from_pile VARCHAR,
+    to_pile VARCHAR,
+    selection VARCHAR,
+    cards VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (game_id, seq)
+);
+
+-- Existing games start their history with the piles they hold now
+INSERT INTO game_events (game_id, seq, kind, to_pile, cards)
+SELECT piles.game_id,
+       ROW_NUMBER() OVER (PARTITION BY piles.game_id ORDER BY piles.name),
+       'pile',
+       piles.name,
+       (SELECT json_group_array(code) FROM (
+           SELECT code FROM pile_cards
+           WHERE pile_cards.game_id = piles.game_id AND pile_cards.pile = piles.name
+           ORDER BY position
+       ))
+FROM piles
@@ -0,0 +1,17 @@
+-- SQLite can't drop columns, so rebuild the table without them
+DELETE FROM game_events WHERE kind IN ('undo', 'redo');
+CREATE TABLE game_events_old (
+    game_id VARCHAR(12) NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    seq INTEGER NOT NULL,
+    kind VARCHAR NOT NULL,
+    from_pile VARCHAR,
+    to_pile VARCHAR,
+    selection VARCHAR,
+    cards VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (game_id, seq)
+);
+INSERT INTO game_events_old
+SELECT game_id, seq, kind, from_pile, to_pile, selection, cards, created_at FROM game_events;
+DROP TABLE game_events;
+ALTER TABLE game_events_old RENAME TO game_events
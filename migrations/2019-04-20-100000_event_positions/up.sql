@@ -0,0 +1,5 @@
+-- Where drawn cards came from and ended up, so moves can be undone exactly.
+-- Undo and redo events point at the draw they reverse or repeat
+ALTER TABLE game_events ADD COLUMN from_positions VARCHAR;
+ALTER TABLE game_events ADD COLUMN to_positions VARCHAR;
+ALTER TABLE game_events ADD COLUMN target INTEGER
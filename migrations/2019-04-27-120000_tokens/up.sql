@@ -0,0 +1,9 @@
+-- Bearer tokens handed out when a game is created or joined, kept as their SHA-256 hash.
+-- A token without a player belongs to whoever created the game
+CREATE TABLE tokens (
+    hash VARCHAR(64) NOT NULL PRIMARY KEY,
+    game_id VARCHAR(12) NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    player_id VARCHAR(12)
+);
+
+CREATE INDEX tokens_game_id ON tokens (game_id)
@@ -0,0 +1,9 @@
+-- SQLite can't drop columns, so rebuild the table without it
+CREATE TABLE piles_old (
+    game_id VARCHAR(12) NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    name VARCHAR NOT NULL,
+    PRIMARY KEY (game_id, name)
+);
+INSERT INTO piles_old SELECT game_id, name FROM piles;
+DROP TABLE piles;
+ALTER TABLE piles_old RENAME TO piles
@@ -0,0 +1,2 @@
+-- Limits on what a pile holds and how cards come and go, as json. Null for none
+ALTER TABLE piles ADD COLUMN rules VARCHAR
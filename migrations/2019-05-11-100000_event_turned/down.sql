@@ -0,0 +1,22 @@
+-- SQLite can't drop columns, so rebuild the table without it
+DELETE FROM game_events WHERE kind IN ('flip', 'face');
+CREATE TABLE game_events_old (
+    game_id VARCHAR(12) NOT NULL REFERENCES games(id) ON DELETE CASCADE,
+    seq INTEGER NOT NULL,
+    kind VARCHAR NOT NULL,
+    from_pile VARCHAR,
+    to_pile VARCHAR,
+    selection VARCHAR,
+    cards VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    from_positions VARCHAR,
+    to_positions VARCHAR,
+    target INTEGER,
+    PRIMARY KEY (game_id, seq)
+);
+INSERT INTO game_events_old
+SELECT game_id, seq, kind, from_pile, to_pile, selection, cards, created_at, from_positions,
+       to_positions, target
+FROM game_events;
+DROP TABLE game_events;
+ALTER TABLE game_events_old RENAME TO game_events
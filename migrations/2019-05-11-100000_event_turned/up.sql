@@ -0,0 +1,2 @@
+-- Cards a draw turned over as they landed, so undoing it can turn them back
+ALTER TABLE game_events ADD COLUMN turned VARCHAR
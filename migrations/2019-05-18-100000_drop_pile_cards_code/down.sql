@@ -0,0 +1 @@
+CREATE INDEX pile_cards_code ON pile_cards (code, pile)
@@ -0,0 +1,2 @@
+-- Piles are only looked through a game at a time, by game_id, so cards need no index by code
+DROP INDEX pile_cards_code
@@ -0,0 +1,286 @@
+use super::cards::*;
+use super::error::CardAPIError;
+use super::events::GameEvent;
+use super::fairness::{sha256_hex, to_hex};
+use super::game::Game;
+use super::models;
+
+use diesel::prelude::*;
+
+use rand::{thread_rng, Rng};
+
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket::Outcome;
+
+// The token from an "Authorization: Bearer <token>" header, if there was one
+pub struct Bearer(Option<String>);
+
+impl<'a, 'r> FromRequest<'a, 'r> for Bearer {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Bearer, String> {
+        let header = match request.headers().get_one("Authorization") {
+            Some(header) => header.trim(),
+            None => return Outcome::Success(Bearer(None)),
+        };
+
+        match header.splitn(2, ' ').collect::<Vec<&str>>().as_slice() {
+            [scheme, token] if scheme.eq_ignore_ascii_case("bearer") => {
+                Outcome::Success(Bearer(Some(token.trim().to_string())))
+            }
+            _ => Outcome::Failure((
+                Status::Unauthorized,
+                format!("Invalid Authorization: {}", header),
+            )),
+        }
+    }
+}
+
+impl Bearer {
+    // Games created before tokens have none, and stay open to anyone
+    pub fn caller(&self, conn: &SqliteConnection, id: &str) -> Result<Caller, CardAPIError> {
+        use super::schema::tokens::dsl::*;
+        use diesel::dsl::*;
+
+        match &self.0 {
+            Some(token) => {
+                let found = tokens
+                    .find(sha256_hex(token.as_bytes()))
+                    .filter(game_id.eq(id))
+                    .get_result::<models::Token>(conn)
+                    .optional()?
+                    .ok_or(CardAPIError::Unauthorized)?;
+                Ok(found.player_id.map_or(Caller::Host, Caller::Player))
+            }
+            None if select(exists(tokens.filter(game_id.eq(id)))).get_result(conn)? => {
+                Ok(Caller::Spectator)
+            }
+            None => Ok(Caller::Host),
+        }
+    }
+}
+
+// Hands out a new token for the game, only its hash is kept
+pub fn issue_token(
+    conn: &SqliteConnection,
+    id: &str,
+    player: Option<&str>,
+) -> Result<String, CardAPIError> {
+    use super::schema::tokens::dsl::*;
+
+    let token = to_hex(&thread_rng().gen::<[u8; 32]>());
+    diesel::insert_into(tokens)
+        .values(models::Token {
+            hash: sha256_hex(token.as_bytes()),
+            game_id: id.to_string(),
+            player_id: player.map(String::from),
+        })
+        .execute(conn)?;
+    Ok(token)
+}
+
+// Who a request comes from, as far as one game is concerned
+#[derive(Clone, Debug, PartialEq)]
+pub enum Caller {
+    // Created the game and runs it, though players' private piles are theirs alone
+    Host,
+    Player(String),
+    // Sent no token, so can only look
+    Spectator,
+}
+
+impl Caller {
+    pub fn player(&self) -> Option<&str> {
+        match self {
+            Caller::Player(id) => Some(id.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn require_token(&self) -> Result<(), CardAPIError> {
+        match self {
+            Caller::Spectator => Err(CardAPIError::Unauthorized),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn require_host(&self) -> Result<(), CardAPIError> {
+        self.require_token()?;
+        match self {
+            Caller::Host => Ok(()),
+            _ => Err(CardAPIError::Forbidden(String::from(
+                "only the game's host can",
+            ))),
+        }
+    }
+
+    // A private pile is off limits to all but its owner, anything else can be looked at
+    pub fn can_see<C: PlayingCard>(
+        &self,
+        game: &Game<C>,
+        pile: &String,
+    ) -> Result<(), CardAPIError> {
+        match (self, game.owner(pile)) {
+            (_, None) => Ok(()),
+            (Caller::Player(id), Some(owner)) if *id == owner.id => Ok(()),
+            _ => Err(CardAPIError::Forbidden(format!("pile {}", pile))),
+        }
+    }
+
+    pub fn can_end_turn<C: PlayingCard>(&self, game: &Game<C>) -> Result<(), CardAPIError> {
+        self.require_token()?;
+        match (self, game.current_player()) {
+            (Caller::Host, _) => Ok(()),
+            (Caller::Player(id), Some(current)) if current.id == *id => Ok(()),
+            _ => Err(CardAPIError::NotYourTurn),
+        }
+    }
+
+    // Turning a shared pile face up shows everyone what's in it, so only the host can
+    pub fn can_turn<C: PlayingCard>(
+        &self,
+        game: &Game<C>,
+        pile: &String,
+        face: Orientation,
+    ) -> Result<(), CardAPIError> {
+        self.can_move(game, pile)?;
+        if face == Orientation::Up
+            && game.owner(pile).is_none()
+            && game.hides_cards(pile, None)?
+            && *self != Caller::Host
+        {
+            return Err(CardAPIError::Forbidden(format!(
+                "only the game's host can turn pile {} face up",
+                pile
+            )));
+        }
+        Ok(())
+    }
+
+    // Undoing or redoing a move takes cards from both of its piles
+    pub fn can_move_again<C: PlayingCard>(
+        &self,
+        game: &Game<C>,
+        event: Option<&GameEvent<C>>,
+    ) -> Result<(), CardAPIError> {
+        self.require_token()?;
+        match event.and_then(GameEvent::touched) {
+            Some((from, to, _)) => {
+                self.can_move(game, from)?;
+                self.can_move(game, to)
+            }
+            None => Ok(()),
+        }
+    }
+
+    // Taking cards from, or rearranging, a pile. Cards can still be dealt onto anyone's.
+    // Players only on their own turn, unless the pile can be used any time. The host
+    // deals whenever they like
+    pub fn can_move<C: PlayingCard>(
+        &self,
+        game: &Game<C>,
+        pile: &String,
+    ) -> Result<(), CardAPIError> {
+        self.require_token()?;
+        self.can_see(game, pile)?;
+        match self {
+            Caller::Player(id) if !game.is_anytime(pile) => match game.current_player() {
+                Some(current) if current.id == *id => Ok(()),
+                _ => Err(CardAPIError::NotYourTurn),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ruleset::RuleSet;
+    use super::*;
+
+    use diesel::connection::SimpleConnection;
+
+    // Two seats with a hand each, the first seat to play
+    fn game() -> (Game, String, String) {
+        let rules = RuleSet {
+            player_count: 2,
+            private_piles: vec![String::from("hand")],
+            ..RuleSet::default()
+        };
+        let mut game: Game = Game::with_seed(rules, 7).unwrap();
+        let first = game.join(None).unwrap().id.clone();
+        let second = game.join(None).unwrap().id.clone();
+        (game, first, second)
+    }
+
+    fn tokens() -> SqliteConnection {
+        let conn = SqliteConnection::establish(":memory:").unwrap();
+        conn.batch_execute(include_str!(
+            "../../migrations/2019-04-27-120000_tokens/up.sql"
+        ))
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn private_piles_are_only_seen_by_their_owner() {
+        let (game, first, second) = game();
+        let (deck, hand) = (String::from("deck"), String::from("hand-0"));
+        assert!(Caller::Player(first).can_see(&game, &hand).is_ok());
+        for caller in vec![Caller::Host, Caller::Player(second), Caller::Spectator] {
+            match caller.can_see(&game, &hand) {
+                Err(CardAPIError::Forbidden(_)) => {}
+                other => panic!("expected Forbidden for {:?}, got {:?}", caller, other),
+            }
+            assert!(caller.can_see(&game, &deck).is_ok());
+        }
+    }
+
+    #[test]
+    fn moves_need_a_token_the_pile_and_the_turn() {
+        let (game, first, second) = game();
+        let (deck, hand) = (String::from("deck"), String::from("hand-0"));
+        assert!(Caller::Host.can_move(&game, &deck).is_ok());
+        assert!(Caller::Player(first.clone()).can_move(&game, &deck).is_ok());
+        assert!(Caller::Player(first).can_move(&game, &hand).is_ok());
+
+        match Caller::Host.can_move(&game, &hand) {
+            Err(CardAPIError::Forbidden(_)) => {}
+            other => panic!("expected Forbidden, got {:?}", other),
+        }
+        match Caller::Player(second).can_move(&game, &deck) {
+            Err(CardAPIError::NotYourTurn) => {}
+            other => panic!("expected NotYourTurn, got {:?}", other),
+        }
+        match Caller::Spectator.can_move(&game, &deck) {
+            Err(CardAPIError::Unauthorized) => {}
+            other => panic!("expected Unauthorized, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tokens_only_work_for_their_own_game() {
+        let conn = tokens();
+        let host = issue_token(&conn, "GAME", None).unwrap();
+        let player = issue_token(&conn, "GAME", Some("PLAYER")).unwrap();
+        let other = issue_token(&conn, "OTHER", None).unwrap();
+
+        let caller = |token: Option<&String>, id| Bearer(token.cloned()).caller(&conn, id);
+        assert_eq!(caller(Some(&host), "GAME").unwrap(), Caller::Host);
+        assert_eq!(
+            caller(Some(&player), "GAME").unwrap(),
+            Caller::Player(String::from("PLAYER"))
+        );
+        assert_eq!(caller(None, "GAME").unwrap(), Caller::Spectator);
+        for (token, id) in vec![(&other, "GAME"), (&host, "OTHER"), (&host, "UNKNOWN")] {
+            match caller(Some(token), id) {
+                Err(CardAPIError::Unauthorized) => {}
+                other => panic!("expected Unauthorized, got {:?}", other),
+            }
+        }
+
+        // Games from before tokens
+        assert_eq!(caller(None, "UNKNOWN").unwrap(), Caller::Host);
+    }
+}
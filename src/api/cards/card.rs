@@ -2,10 +2,82 @@ use super::*;
 
 use serde::*;
 
-#[derive(Clone, Eq, PartialEq)]
+use std::cmp::Ordering;
+
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct Card {
-    pub suit: CardSuit,
+    // Jokers have no suit
+    pub suit: Option<CardSuit>,
     pub value: CardValue,
+    // Which deck of a multi-deck shoe the card came from, so identical faces stay distinct
+    pub deck: usize,
+}
+
+impl Card {
+    pub fn is_joker(&self) -> bool {
+        match self.value {
+            CardValue::RedJoker | CardValue::BlackJoker => true,
+            _ => false,
+        }
+    }
+
+    pub fn compare<R: CardRanking>(&self, other: &Card, ranking: &R) -> Ordering {
+        ranking.compare(self, other)
+    }
+
+    pub fn beats<R: CardRanking>(&self, other: &Card, ranking: &R) -> bool {
+        self.compare(other, ranking) == Ordering::Greater
+    }
+
+    pub fn points<R: CardRanking>(&self, ranking: &R) -> u32 {
+        ranking.points(self)
+    }
+}
+
+impl PlayingCard for Card {
+    type Suit = CardSuit;
+    type Value = CardValue;
+    type Ranking = Valuation;
+    type Definition = DeckDefinition;
+
+    const TYPE: CardType = CardType::French;
+
+    fn suit(&self) -> Option<&CardSuit> {
+        self.suit.as_ref()
+    }
+
+    fn value(&self) -> &CardValue {
+        &self.value
+    }
+
+    fn deck(&self) -> usize {
+        self.deck
+    }
+
+    fn with_deck(&self, deck: usize) -> Card {
+        Card {
+            deck,
+            ..self.clone()
+        }
+    }
+
+    fn faces(definition: &DeckDefinition) -> Vec<Card> {
+        definition.faces()
+    }
+
+    fn jokers() -> Vec<Card> {
+        ALL_JOKERS()
+    }
+
+    fn colour(&self) -> Option<&'static str> {
+        match (&self.value, &self.suit) {
+            (CardValue::RedJoker, _) => Some("red"),
+            (CardValue::BlackJoker, _) => Some("black"),
+            (_, Some(CardSuit::Hearts)) | (_, Some(CardSuit::Diamonds)) => Some("red"),
+            (_, Some(CardSuit::Clubs)) | (_, Some(CardSuit::Spades)) => Some("black"),
+            (_, None) => None,
+        }
+    }
 }
 
 impl std::fmt::Debug for Card {
@@ -16,18 +88,29 @@ impl std::fmt::Debug for Card {
 
 impl HasStringCode for Card {
     fn to_str(&self) -> String {
-        format!("{}{}", self.value.to_str(), self.suit.to_str())
+        format!(
+            "{}{}{}",
+            self.value.to_str(),
+            self.suit.as_ref().map_or(String::new(), CardSuit::to_str),
+            deck_suffix(self.deck)
+        )
     }
 
     fn from_str(code: String) -> Option<Card> {
-        if code.len() > 2 {
-            None
+        let mut chars = code.chars();
+        let first = chars.next()?;
+
+        // Jokers are "XR" or "XB", a two character value with no suit
+        let (value, suit) = if first == 'X' {
+            (CardValue::from_str(format!("X{}", chars.next()?))?, None)
         } else {
-            Some(Card {
-                value: CardValue::from_str(code.chars().nth(0)?.to_string())?,
-                suit: CardSuit::from_str(code.chars().nth(1)?.to_string())?,
-            })
-        }
+            let value = CardValue::from_str(first.to_string())?;
+            (value, Some(CardSuit::from_str(chars.next()?.to_string())?))
+        };
+
+        let deck = parse_deck(chars.as_str())?;
+
+        Some(Card { suit, value, deck })
     }
 }
 
@@ -36,7 +119,7 @@ impl Serialize for Card {
     where
         S: Serializer,
     {
-        serializer.serialize_str(format!("{}{}", self.value.to_str(), self.suit.to_str()).as_str())
+        serializer.serialize_str(self.to_str().as_str())
     }
 }
 
@@ -48,3 +131,38 @@ impl<'de> Deserialize<'de> for Card {
         deserializer.deserialize_str(super::CodeVisitor::<Self>::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(code: &str) -> Option<Card> {
+        Card::from_str(code.to_string())
+    }
+
+    #[test]
+    fn codes_carry_the_deck_after_the_first() {
+        let copy = card("AS1").unwrap();
+        assert_eq!(copy.deck(), 1);
+        assert_eq!(copy.to_str(), "AS1");
+        assert_eq!(card("AS").unwrap().deck(), 0);
+        assert_ne!(copy, card("AS").unwrap());
+        assert!(copy.same_face(&card("AS").unwrap()));
+
+        // The first deck is only ever written without an index
+        for code in &["AS0", "AS01", "AS-1", "AS1X", "ASS"] {
+            assert!(card(code).is_none(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn jokers_have_no_suit_and_can_be_copied() {
+        let joker = card("XR1").unwrap();
+        assert_eq!(joker.suit(), None);
+        assert_eq!(joker.deck(), 1);
+        assert_eq!(joker.to_str(), "XR1");
+        assert!(card("XR").is_some());
+        assert!(card("XS").is_none());
+        assert!(card("XR0").is_none());
+    }
+}
@@ -2,83 +2,289 @@ use super::*;
 
 use std::slice::Iter;
 
+use rand::{thread_rng, Rng};
+
 use serde::de::{Deserialize, Deserializer, SeqAccess, Visitor};
 use serde::ser::{Serialize, SerializeSeq, Serializer};
 
 #[derive(Clone, Debug)]
-pub struct CardCollection {
-    pub(self) cards: Vec<Card>,
+pub struct CardCollection<C: PlayingCard = Card> {
+    pub(self) cards: Vec<C>,
 }
 
-impl CardCollection {
-    pub fn new() -> CardCollection {
+impl<C: PlayingCard> CardCollection<C> {
+    pub fn new() -> CardCollection<C> {
         CardCollection { cards: Vec::new() }
     }
+    // Only cards from `cards` can be picked, each of them once
+    pub fn from_cards<R: Rng + ?Sized>(
+        cards: &Vec<C>,
+        selection: &CardSelection<C>,
+        rng: &mut R,
+    ) -> Result<CardCollection<C>, CardAPIError> {
+        CardCollection::from(cards.clone()).selected(selection, rng)
+    }
     pub fn remaining(&self) -> usize {
         self.cards.len()
     }
-    pub fn contains(&self, c: &Card) -> bool {
+    pub fn iter(&self) -> Iter<C> {
+        self.cards.iter()
+    }
+    pub fn contains(&self, c: &C) -> bool {
         self.cards.contains(c)
     }
-    pub fn draw(
+    pub fn score<R: CardRanking<C>>(&self, ranking: &R) -> u32 {
+        self.cards.iter().map(|c| ranking.points(c)).sum()
+    }
+    // Random selections here don't come from any game's stream, so peeking can't
+    // give away what a draw will pick
+    pub fn peek(&self, selection: &CardSelection<C>) -> Result<CardCollection<C>, CardAPIError> {
+        self.selected(selection, &mut thread_rng())
+    }
+    pub fn selected<R: Rng + ?Sized>(
+        &self,
+        selection: &CardSelection<C>,
+        rng: &mut R,
+    ) -> Result<CardCollection<C>, CardAPIError> {
+        Ok(CardCollection {
+            cards: self.select(selection, rng)?,
+        })
+    }
+    // Returns the cards drawn, in the order they were put into `into`
+    pub fn draw<R: Rng + ?Sized>(
+        &mut self,
+        selection: &CardSelection<C>,
+        into: &mut CardCollection<C>,
+        placement: &Placement,
+        rng: &mut R,
+    ) -> Result<CardCollection<C>, CardAPIError> {
+        let to_draw = self.select(selection, rng)?;
+
+        if into.cards.iter().any(|c| to_draw.contains(c)) {
+            return Err(CardAPIError::CardAlreadyInCollection);
+        }
+
+        into.put(to_draw.clone(), placement, rng)?;
+        self.cards.retain(|c| !to_draw.contains(&c));
+
+        Ok(CardCollection { cards: to_draw })
+    }
+
+    pub fn shuffle<R: Rng + ?Sized>(
+        &mut self,
+        method: &ShuffleMethod,
+        rng: &mut R,
+    ) -> Result<(), CardAPIError> {
+        method.apply(&mut self.cards, rng)
+    }
+    pub fn reverse(&mut self) {
+        self.cards.reverse()
+    }
+    // Ties keep their current order, cards without a suit go below the rest
+    pub fn sort<R: CardRanking<C>>(&mut self, by: &SortBy, ranking: &R) {
+        match by {
+            SortBy::Suit => self.cards.sort_by(|a, b| a.suit().cmp(&b.suit())),
+            SortBy::Value => self.cards.sort_by(|a, b| ranking.compare(a, b)),
+            SortBy::Both => self
+                .cards
+                .sort_by(|a, b| a.suit().cmp(&b.suit()).then(ranking.compare(a, b))),
+        }
+    }
+    // Where each of the cards sits in this collection
+    pub fn positions(&self, cards: &CardCollection<C>) -> Vec<usize> {
+        cards
+            .cards
+            .iter()
+            .filter_map(|c| self.cards.iter().position(|x| x == c))
+            .collect()
+    }
+    // Takes out exactly these cards
+    pub fn remove(&mut self, cards: &CardCollection<C>) -> Result<(), CardAPIError> {
+        if cards.cards.iter().any(|c| !self.cards.contains(c)) {
+            return Err(CardAPIError::CardNotInCollection);
+        }
+
+        self.cards.retain(|c| !cards.cards.contains(c));
+        Ok(())
+    }
+    // Puts each card back at its position, positions being where the cards end up
+    pub fn insert_at(
         &mut self,
-        selection: &CardSelection,
-        into: &mut CardCollection,
+        cards: &CardCollection<C>,
+        positions: &Vec<usize>,
     ) -> Result<(), CardAPIError> {
-        let mut to_draw = CardCollection::select_cards(&self.cards, selection)?;
+        if cards.cards.iter().any(|c| self.cards.contains(c)) {
+            return Err(CardAPIError::CardAlreadyInCollection);
+        }
 
-        if self.cards.iter().filter(|c| to_draw.contains(c)).count() == to_draw.len()
-            && into.cards.iter().filter(|c| to_draw.contains(c)).count() == 0
+        let mut placed: Vec<(usize, &C)> = positions.iter().cloned().zip(&cards.cards).collect();
+        placed.sort_by_key(|(position, _)| *position);
+        let len = self.cards.len();
+        if positions.len() != cards.cards.len()
+            || placed
+                .iter()
+                .enumerate()
+                .any(|(i, (position, _))| *position > len + i)
         {
-            self.cards.retain(|c| !to_draw.contains(&c));
-            into.cards.append(&mut to_draw);
+            return Err(CardAPIError::InvalidSelection(String::from(
+                "card positions out of range",
+            )));
         }
 
+        for (position, card) in placed {
+            self.cards.insert(position, card.clone());
+        }
         Ok(())
     }
 
-    fn select_cards(
-        cards: &Vec<Card>,
-        selection: &CardSelection,
-    ) -> Result<Vec<Card>, CardAPIError> {
+    fn put<R: Rng + ?Sized>(
+        &mut self,
+        cards: Vec<C>,
+        placement: &Placement,
+        rng: &mut R,
+    ) -> Result<(), CardAPIError> {
+        let at = match placement {
+            Placement::Top => self.cards.len(),
+            Placement::Bottom => 0,
+            Placement::Index(index) if *index > self.cards.len() => {
+                return Err(CardAPIError::InvalidSelection(format!(
+                    "index {} is past the bottom of a pile of {}",
+                    index,
+                    self.cards.len()
+                )))
+            }
+            Placement::Index(index) => self.cards.len() - index,
+            Placement::Random => {
+                for card in cards {
+                    let at = rng.gen_range(0, self.cards.len() + 1);
+                    self.cards.insert(at, card);
+                }
+                return Ok(());
+            }
+        };
+        self.cards.splice(at..at, cards);
+        Ok(())
+    }
+
+    fn select<R: Rng + ?Sized>(
+        &self,
+        selection: &CardSelection<C>,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
+        let selected = CardCollection::select_cards(&self.cards, selection, rng)?;
+
+        // Every card has its own identity, even in a multi-deck shoe, so each one
+        // selected must be in this collection exactly once
+        if self.cards.iter().filter(|c| selected.contains(c)).count() != selected.len() {
+            return Err(CardAPIError::CardNotInCollection);
+        }
+
+        Ok(selected)
+    }
+
+    fn select_cards<R: Rng + ?Sized>(
+        cards: &Vec<C>,
+        selection: &CardSelection<C>,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
         match selection {
             CardSelection::Empty => Ok(Vec::new()),
-            CardSelection::All(shuffled) => CardCollection::select_all(cards, shuffled),
-            CardSelection::Random(n) => CardCollection::select_random(cards, n),
+            CardSelection::All(shuffled) => CardCollection::select_all(cards, shuffled, rng),
+            CardSelection::Random(n) => CardCollection::select_random(cards, n, rng),
             CardSelection::Bottom(n) => CardCollection::select_bottom(cards, n),
             CardSelection::Top(n) => CardCollection::select_top(cards, n),
             CardSelection::Filter { suits, values } => {
                 CardCollection::select_filter(cards, suits, values)
             }
             CardSelection::Cards(collection) => Ok(collection.cards.clone()),
+            CardSelection::And(selections) => CardCollection::select_and(cards, selections, rng),
+            CardSelection::Or(selections) => CardCollection::select_or(cards, selections, rng),
+            CardSelection::Not(selection) => CardCollection::select_not(cards, selection, rng),
+            CardSelection::Then(selections) => CardCollection::select_then(cards, selections, rng),
+        }
+    }
+
+    fn select_and<R: Rng + ?Sized>(
+        from: &Vec<C>,
+        selections: &Vec<CardSelection<C>>,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
+        let mut selected = from.clone();
+        for selection in selections {
+            let matched = CardCollection::select_cards(from, selection, rng)?;
+            selected.retain(|c| matched.contains(c));
         }
+        Ok(selected)
     }
 
-    fn select_all(from: &Vec<Card>, shuffled: &bool) -> Result<Vec<Card>, CardAPIError> {
+    fn select_or<R: Rng + ?Sized>(
+        from: &Vec<C>,
+        selections: &Vec<CardSelection<C>>,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
+        let mut matched = Vec::new();
+        for selection in selections {
+            matched.extend(CardCollection::select_cards(from, selection, rng)?);
+        }
+        Ok(from
+            .iter()
+            .filter(|c| matched.contains(c))
+            .cloned()
+            .collect())
+    }
+
+    fn select_not<R: Rng + ?Sized>(
+        from: &Vec<C>,
+        selection: &CardSelection<C>,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
+        let matched = CardCollection::select_cards(from, selection, rng)?;
+        Ok(from
+            .iter()
+            .filter(|c| !matched.contains(c))
+            .cloned()
+            .collect())
+    }
+
+    fn select_then<R: Rng + ?Sized>(
+        from: &Vec<C>,
+        selections: &Vec<CardSelection<C>>,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
+        let mut selected = from.clone();
+        for selection in selections {
+            selected = CardCollection::select_cards(&selected, selection, rng)?;
+        }
+        Ok(selected)
+    }
+
+    fn select_all<R: Rng + ?Sized>(
+        from: &Vec<C>,
+        shuffled: &bool,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
         if *shuffled {
-            CardCollection::select_random(from, &from.len())
+            CardCollection::select_random(from, &from.len(), rng)
         } else {
-            println!("this ran...");
             Ok(from.clone())
         }
     }
 
-    fn select_random(from: &Vec<Card>, n: &usize) -> Result<Vec<Card>, CardAPIError> {
+    fn select_random<R: Rng + ?Sized>(
+        from: &Vec<C>,
+        n: &usize,
+        rng: &mut R,
+    ) -> Result<Vec<C>, CardAPIError> {
         use rand::seq::SliceRandom;
-        use rand::thread_rng;
 
         if *n <= from.len() {
-            Ok(from
-                .as_slice()
-                .choose_multiple(&mut thread_rng(), *n)
-                .cloned()
-                .collect())
+            Ok(from.as_slice().choose_multiple(rng, *n).cloned().collect())
         } else {
             Err(CardAPIError::NotEnoughCards)
         }
     }
 
-    fn select_top(from: &Vec<Card>, n: &usize) -> Result<Vec<Card>, CardAPIError> {
+    fn select_top(from: &Vec<C>, n: &usize) -> Result<Vec<C>, CardAPIError> {
         if (*n <= from.len()) {
             let start = if *n > from.len() { 0 } else { from.len() - n };
             Ok(from[start..].to_vec())
@@ -87,7 +293,7 @@ impl CardCollection {
         }
     }
 
-    fn select_bottom(from: &Vec<Card>, n: &usize) -> Result<Vec<Card>, CardAPIError> {
+    fn select_bottom(from: &Vec<C>, n: &usize) -> Result<Vec<C>, CardAPIError> {
         if *n < from.len() {
             let end = std::cmp::min(*n, from.len());
             Ok(from[..end].to_vec())
@@ -97,12 +303,12 @@ impl CardCollection {
     }
 
     fn select_filter(
-        from: &Vec<Card>,
-        suits: &StringCodes<CardSuit>,
-        values: &StringCodes<CardValue>,
-    ) -> Result<Vec<Card>, CardAPIError> {
-        let test_suit = |c: &Card| suits.len() == 0 || suits.contains(&c.suit);
-        let test_value = |c: &Card| values.len() == 0 || values.contains(&c.value);
+        from: &Vec<C>,
+        suits: &StringCodes<C::Suit>,
+        values: &StringCodes<C::Value>,
+    ) -> Result<Vec<C>, CardAPIError> {
+        let test_suit = |c: &C| suits.len() == 0 || c.suit().map_or(false, |s| suits.contains(s));
+        let test_value = |c: &C| values.len() == 0 || values.contains(c.value());
 
         let expected = suits.len() * values.len();
 
@@ -116,15 +322,13 @@ impl CardCollection {
     }
 }
 
-impl From<CardSelection> for CardCollection {
-    fn from(selection: CardSelection) -> Self {
-        CardCollection {
-            cards: CardCollection::select_cards(&ALL_CARDS(), &selection).unwrap(),
-        }
+impl<C: PlayingCard> From<Vec<C>> for CardCollection<C> {
+    fn from(cards: Vec<C>) -> Self {
+        CardCollection { cards }
     }
 }
 
-impl Serialize for CardCollection {
+impl<C: PlayingCard> Serialize for CardCollection<C> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -137,14 +341,16 @@ impl Serialize for CardCollection {
     }
 }
 
-impl<'de> Deserialize<'de> for CardCollection {
+impl<'de, C: PlayingCard> Deserialize<'de> for CardCollection<C> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        struct CardCollectionVisitor;
-        impl<'de> Visitor<'de> for CardCollectionVisitor {
-            type Value = CardCollection;
+        struct CardCollectionVisitor<C> {
+            phantom: std::marker::PhantomData<C>,
+        }
+        impl<'de, C: PlayingCard> Visitor<'de> for CardCollectionVisitor<C> {
+            type Value = CardCollection<C>;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
                 formatter.write_str("a card code")
@@ -164,6 +370,118 @@ impl<'de> Deserialize<'de> for CardCollection {
             }
         }
 
-        deserializer.deserialize_seq(CardCollectionVisitor)
+        deserializer.deserialize_seq(CardCollectionVisitor {
+            phantom: std::marker::PhantomData::<C>,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // Bottom first, so 2H is on top
+    fn pile() -> CardCollection {
+        collection(&["AS", "2S", "3S", "AH", "2H"])
+    }
+
+    fn collection(codes: &[&str]) -> CardCollection {
+        let cards: Vec<Card> = codes
+            .iter()
+            .map(|code| Card::from_str(code.to_string()).unwrap())
+            .collect();
+        CardCollection::from(cards)
+    }
+
+    fn codes(cards: &CardCollection) -> Vec<String> {
+        cards.iter().map(Card::to_str).collect()
+    }
+
+    fn picked(selection: CardSelection) -> Vec<String> {
+        let mut rng = StdRng::from_seed([0; 32]);
+        codes(&pile().selected(&selection, &mut rng).unwrap())
+    }
+
+    fn suits(codes: &str) -> CardSelection {
+        CardSelection::Filter {
+            suits: StringCodes::from_str(codes.to_string()).unwrap(),
+            values: StringCodes::default(),
+        }
+    }
+
+    #[test]
+    fn and_keeps_the_cards_every_selection_matches() {
+        let selection = CardSelection::And(vec![suits("S"), CardSelection::Top(3)]);
+        assert_eq!(picked(selection), vec!["3S"]);
+        assert_eq!(picked(CardSelection::And(vec![])).len(), 5);
+    }
+
+    #[test]
+    fn or_keeps_pile_order_without_repeats() {
+        let selection = CardSelection::Or(vec![
+            CardSelection::Cards(collection(&["AH"])),
+            CardSelection::Bottom(1),
+            CardSelection::Cards(collection(&["AS"])),
+        ]);
+        assert_eq!(picked(selection), vec!["AS", "AH"]);
+    }
+
+    #[test]
+    fn not_keeps_the_cards_left_out() {
+        let selection = CardSelection::Not(Box::new(suits("H")));
+        assert_eq!(picked(selection), vec!["AS", "2S", "3S"]);
+    }
+
+    #[test]
+    fn then_picks_from_what_came_before() {
+        let selection = CardSelection::Then(vec![suits("S"), CardSelection::Top(2)]);
+        assert_eq!(picked(selection), vec!["2S", "3S"]);
+
+        let selection = CardSelection::Then(vec![suits("H"), CardSelection::Top(3)]);
+        match pile().peek(&selection) {
+            Err(CardAPIError::NotEnoughCards) => {}
+            other => panic!("expected NotEnoughCards, got {:?}", other),
+        }
+    }
+
+    fn put_at(index: usize) -> Result<Vec<String>, CardAPIError> {
+        let mut cards = pile();
+        let placement = Placement::Index(index);
+        let mut rng = StdRng::from_seed([0; 32]);
+        cards.put(
+            vec![Card::from_str(String::from("KC")).unwrap()],
+            &placement,
+            &mut rng,
+        )?;
+        Ok(codes(&cards))
+    }
+
+    #[test]
+    fn index_counts_down_from_the_top() {
+        assert_eq!(put_at(0).unwrap(), vec!["AS", "2S", "3S", "AH", "2H", "KC"]);
+        assert_eq!(put_at(1).unwrap(), vec!["AS", "2S", "3S", "AH", "KC", "2H"]);
+        assert_eq!(put_at(5).unwrap(), vec!["KC", "AS", "2S", "3S", "AH", "2H"]);
+        match put_at(6) {
+            Err(CardAPIError::InvalidSelection(_)) => {}
+            other => panic!("expected InvalidSelection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn drawn_cards_keep_their_order_at_an_index() {
+        let (mut from, mut into) = (collection(&["QD", "KD"]), pile());
+        let mut rng = StdRng::from_seed([0; 32]);
+        from.draw(
+            &CardSelection::All(false),
+            &mut into,
+            &Placement::Index(2),
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(from.remaining(), 0);
+        assert_eq!(codes(&into), vec!["AS", "2S", "3S", "QD", "KD", "AH", "2H"]);
     }
 }
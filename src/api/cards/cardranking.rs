@@ -0,0 +1,100 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+pub trait CardRanking<C: PlayingCard = Card> {
+    // Position of a value in the ranking, higher beats lower
+    fn rank(&self, value: &C::Value) -> u32;
+    // Points a card is worth when scoring a pile
+    fn points(&self, card: &C) -> u32;
+
+    fn compare(&self, a: &C, b: &C) -> Ordering {
+        self.rank(a.value()).cmp(&self.rank(b.value()))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PictureValues {
+    // Jack, Queen and King are all worth 10
+    Ten,
+    // Jack, Queen and King are worth 11, 12 and 13
+    Face,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Valuation {
+    pub ace_high: bool,
+    pub pictures: PictureValues,
+    // Per-game overrides, e.g { "A": 11, "K": 0 }
+    pub points: HashMap<CardValue, u32>,
+}
+
+impl Default for Valuation {
+    fn default() -> Self {
+        Valuation {
+            ace_high: false,
+            pictures: PictureValues::Face,
+            points: HashMap::new(),
+        }
+    }
+}
+
+impl CardRanking for Valuation {
+    fn rank(&self, value: &CardValue) -> u32 {
+        match value {
+            CardValue::Ace if self.ace_high => 14,
+            CardValue::Ace => 1,
+            CardValue::Two => 2,
+            CardValue::Three => 3,
+            CardValue::Four => 4,
+            CardValue::Five => 5,
+            CardValue::Six => 6,
+            CardValue::Seven => 7,
+            CardValue::Eight => 8,
+            CardValue::Nine => 9,
+            CardValue::Ten => 10,
+            CardValue::Jack => 11,
+            CardValue::Queen => 12,
+            CardValue::King => 13,
+            CardValue::RedJoker | CardValue::BlackJoker => 15,
+        }
+    }
+
+    fn points(&self, card: &Card) -> u32 {
+        if let Some(points) = self.points.get(&card.value) {
+            return *points;
+        }
+
+        match card.value {
+            CardValue::Jack | CardValue::Queen | CardValue::King
+                if self.pictures == PictureValues::Ten =>
+            {
+                10
+            }
+            CardValue::RedJoker | CardValue::BlackJoker => 0,
+            _ => self.rank(&card.value),
+        }
+    }
+}
+
+// The ranking of card types that only have their points to choose, each card type
+// ranking and scoring its own values
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields, bound = "")]
+pub struct Points<C: PlayingCard> {
+    // Per-game overrides of the card type's own points
+    pub points: HashMap<C::Value, u32>,
+}
+
+impl<C: PlayingCard> Default for Points<C> {
+    fn default() -> Self {
+        Points {
+            points: HashMap::new(),
+        }
+    }
+}
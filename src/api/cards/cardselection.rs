@@ -4,8 +4,8 @@ use rand::seq::SliceRandom;
 use rand::thread_rng;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(rename_all = "lowercase")]
-pub enum CardSelection {
+#[serde(rename_all = "lowercase", bound = "")]
+pub enum CardSelection<C: PlayingCard = Card> {
     Empty,
     All(bool),
     Top(usize),
@@ -13,37 +13,229 @@ pub enum CardSelection {
     Random(usize),
     Filter {
         #[serde(default)]
-        suits: StringCodes<CardSuit>,
+        suits: StringCodes<C::Suit>,
         #[serde(default)]
-        values: StringCodes<CardValue>,
+        values: StringCodes<C::Value>,
     },
-    Cards(CardCollection),
+    Cards(CardCollection<C>),
+    // Cards matched by every selection
+    And(Vec<CardSelection<C>>),
+    // Cards matched by any selection
+    Or(Vec<CardSelection<C>>),
+    // Cards not matched by the selection
+    Not(Box<CardSelection<C>>),
+    // Each selection picks from the cards chosen by the one before it
+    Then(Vec<CardSelection<C>>),
+}
+
+// Which kind of selection one is, written as in a CardSelection, e.g "top"
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionKind {
+    Empty,
+    All,
+    Top,
+    Bottom,
+    Random,
+    Filter,
+    Cards,
+    And,
+    Or,
+    Not,
+    Then,
 }
 
-const LIMIT: u64 = 256;
+impl SelectionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SelectionKind::Empty => "empty",
+            SelectionKind::All => "all",
+            SelectionKind::Top => "top",
+            SelectionKind::Bottom => "bottom",
+            SelectionKind::Random => "random",
+            SelectionKind::Filter => "filter",
+            SelectionKind::Cards => "cards",
+            SelectionKind::And => "and",
+            SelectionKind::Or => "or",
+            SelectionKind::Not => "not",
+            SelectionKind::Then => "then",
+        }
+    }
+}
 
-use rocket::data::{self, FromDataSimple};
-use rocket::http::{ContentType, Status};
-use rocket::{Data, Outcome, Outcome::*, Request};
-use std::io::Read;
+impl<C: PlayingCard> CardSelection<C> {
+    pub fn kind(&self) -> SelectionKind {
+        match self {
+            CardSelection::Empty => SelectionKind::Empty,
+            CardSelection::All(_) => SelectionKind::All,
+            CardSelection::Top(_) => SelectionKind::Top,
+            CardSelection::Bottom(_) => SelectionKind::Bottom,
+            CardSelection::Random(_) => SelectionKind::Random,
+            CardSelection::Filter { .. } => SelectionKind::Filter,
+            CardSelection::Cards(_) => SelectionKind::Cards,
+            CardSelection::And(_) => SelectionKind::And,
+            CardSelection::Or(_) => SelectionKind::Or,
+            CardSelection::Not(_) => SelectionKind::Not,
+            CardSelection::Then(_) => SelectionKind::Then,
+        }
+    }
 
-impl FromDataSimple for CardSelection {
+    // Picks by position alone, so it tells nothing about cards that can't be seen
+    pub fn is_blind(&self) -> bool {
+        match self {
+            CardSelection::Filter { .. } | CardSelection::Cards(_) => false,
+            CardSelection::And(selections)
+            | CardSelection::Or(selections)
+            | CardSelection::Then(selections) => selections.iter().all(CardSelection::is_blind),
+            CardSelection::Not(selection) => selection.is_blind(),
+            _ => true,
+        }
+    }
+}
+
+use rocket::request::{FromQuery, Query};
+
+// A selection's query params, kept until the game's card type says what codes they hold
+#[derive(Debug)]
+pub struct SelectionQuery(Vec<(String, String)>);
+
+impl<'q> FromQuery<'q> for SelectionQuery {
     type Error = String;
 
-    fn from_data(req: &Request, data: Data) -> data::Outcome<Self, String> {
-        let person_ct = ContentType::JSON;
-        if req.content_type() != Some(&person_ct) {
-            return Outcome::Forward(data);
+    fn from_query(query: Query<'q>) -> Result<Self, Self::Error> {
+        query
+            .map(|item| {
+                let value = item
+                    .value
+                    .url_decode()
+                    .map_err(|_| format!("Invalid value for {}", item.key))?;
+                Ok((item.key.to_string(), value))
+            })
+            .collect::<Result<Vec<(String, String)>, String>>()
+            .map(SelectionQuery)
+    }
+}
+
+impl SelectionQuery {
+    // Builds a selection from query params, e.g ?suits=H,D&values=A, ?cards=AH,AD,
+    // ?random=10, ?top=10 or ?bottom=10. Top and bottom default to 1 card
+    pub fn selection<C: PlayingCard>(&self) -> Result<CardSelection<C>, String> {
+        let mut suits = None;
+        let mut values = None;
+        let mut selections = Vec::new();
+
+        for (key, value) in &self.0 {
+            let count = || -> Result<usize, String> {
+                if value.is_empty() {
+                    Ok(1)
+                } else {
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid count for {}: {}", key, value))
+                }
+            };
+            let codes = || format!("Invalid codes for {}: {}", key, value);
+
+            match key.as_str() {
+                "suits" => suits = Some(StringCodes::from_str(value.clone()).ok_or_else(codes)?),
+                "values" => values = Some(StringCodes::from_str(value.clone()).ok_or_else(codes)?),
+                "cards" => {
+                    let cards: StringCodes<C> =
+                        StringCodes::from_str(value.clone()).ok_or_else(codes)?;
+                    selections.push(CardSelection::Cards(CardCollection::from(
+                        cards.iter().cloned().collect::<Vec<C>>(),
+                    )))
+                }
+                "random" => selections.push(CardSelection::Random(count()?)),
+                "top" => selections.push(CardSelection::Top(count()?)),
+                "bottom" => selections.push(CardSelection::Bottom(count()?)),
+                _ => {}
+            }
+        }
+
+        let filter = if suits.is_some() || values.is_some() {
+            Some(CardSelection::Filter {
+                suits: suits.unwrap_or_default(),
+                values: values.unwrap_or_default(),
+            })
+        } else {
+            None
+        };
+
+        // A filter narrows down whatever else was asked for, e.g ?suits=H&top=3
+        match (filter, selections.len()) {
+            (None, 0) => Err(String::from("No card selection given")),
+            (Some(filter), 0) => Ok(filter),
+            (None, 1) => Ok(selections.remove(0)),
+            (Some(filter), 1) => Ok(CardSelection::Then(vec![filter, selections.remove(0)])),
+            _ => Err(String::from(
+                "Only one of cards, random, top or bottom can be used",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection(params: &[(&str, &str)]) -> Result<CardSelection, String> {
+        let params = params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        SelectionQuery(params).selection()
+    }
+
+    #[test]
+    fn counts_default_to_one_card() {
+        match selection(&[("top", "")]) {
+            Ok(CardSelection::Top(1)) => {}
+            other => panic!("expected top 1, got {:?}", other),
         }
+        match selection(&[("random", "10")]) {
+            Ok(CardSelection::Random(10)) => {}
+            other => panic!("expected random 10, got {:?}", other),
+        }
+        assert!(selection(&[("bottom", "two")]).is_err());
+    }
 
-        let mut string = String::new();
-        if let Err(e) = data.open().take(LIMIT).read_to_string(&mut string) {
-            return Failure((Status::InternalServerError, format!("{:?}", e)));
+    #[test]
+    fn cards_are_read_as_codes() {
+        match selection(&[("cards", "AH,AD1")]) {
+            Ok(CardSelection::Cards(cards)) => {
+                let codes: Vec<String> = cards.iter().map(Card::to_str).collect();
+                assert_eq!(codes, vec!["AH", "AD1"]);
+            }
+            other => panic!("expected cards, got {:?}", other),
         }
+        assert!(selection(&[("cards", "AH,ZZ")]).is_err());
+        assert!(selection(&[("suits", "Q")]).is_err());
+    }
 
-        match serde_json::from_str::<CardSelection>(string.as_str()) {
-            Ok(result) => Outcome::Success(result),
-            Err(e) => Failure((Status::InternalServerError, format!("{:?}", e))),
+    #[test]
+    fn a_filter_narrows_down_the_other_selection() {
+        match selection(&[("suits", "H,D"), ("values", "A")]) {
+            Ok(CardSelection::Filter { suits, values }) => {
+                assert_eq!(suits.iter().count(), 2);
+                assert_eq!(values.iter().count(), 1);
+            }
+            other => panic!("expected a filter, got {:?}", other),
+        }
+        match selection(&[("top", "3"), ("suits", "H")]) {
+            Ok(CardSelection::Then(selections)) => {
+                let kinds: Vec<SelectionKind> =
+                    selections.iter().map(CardSelection::kind).collect();
+                assert_eq!(kinds, vec![SelectionKind::Filter, SelectionKind::Top]);
+            }
+            other => panic!("expected a filter then top, got {:?}", other),
         }
     }
+
+    #[test]
+    fn only_one_selection_besides_a_filter() {
+        assert!(selection(&[]).is_err());
+        assert!(selection(&[("unknown", "1")]).is_err());
+        assert!(selection(&[("top", "1"), ("cards", "AS")]).is_err());
+    }
 }
@@ -2,7 +2,7 @@ use super::*;
 
 use serde::*;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum CardSuit {
     Hearts,
     Clubs,
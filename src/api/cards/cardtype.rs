@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+// The kinds of card a game can be played with, see PlayingCard
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CardType {
+    French,
+    Tarot,
+    Uno,
+    Hanafuda,
+}
+
+impl Default for CardType {
+    fn default() -> Self {
+        CardType::French
+    }
+}
@@ -2,7 +2,7 @@ use super::*;
 
 use serde::*;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum CardValue {
     Ace,
     Two,
@@ -17,6 +17,9 @@ pub enum CardValue {
     Jack,
     Queen,
     King,
+    // Jokers carry their colour here, having no suit
+    RedJoker,
+    BlackJoker,
 }
 
 impl HasStringCode for CardValue {
@@ -35,6 +38,8 @@ impl HasStringCode for CardValue {
             CardValue::Jack => "J",
             CardValue::Queen => "Q",
             CardValue::King => "K",
+            CardValue::RedJoker => "XR",
+            CardValue::BlackJoker => "XB",
         };
         s.to_string()
     }
@@ -54,6 +59,8 @@ impl HasStringCode for CardValue {
             "J" => Some(CardValue::Jack),
             "Q" => Some(CardValue::Queen),
             "K" => Some(CardValue::King),
+            "XR" => Some(CardValue::RedJoker),
+            "XB" => Some(CardValue::BlackJoker),
             _ => None,
         }
     }
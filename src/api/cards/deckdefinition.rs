@@ -0,0 +1,73 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum DeckDefinition {
+    // Standard 52 card deck
+    French,
+    // 32 cards, 7 to A in each suit
+    Piquet,
+    // 48 cards, two of each 9 to A in each suit
+    Pinochle,
+    // 24 cards, 9 to A in each suit
+    Euchre,
+    // Any list of card codes, repeated codes become separate copies
+    Custom(StringCodes<Card>),
+}
+
+impl Default for DeckDefinition {
+    fn default() -> Self {
+        DeckDefinition::French
+    }
+}
+
+impl DeckDefinition {
+    // The faces making up a single deck, possibly with repeats
+    pub fn faces(&self) -> Vec<Card> {
+        let from = |lowest: CardValue| -> Vec<Card> {
+            ALL_CARDS()
+                .into_iter()
+                .filter(|c| c.value == CardValue::Ace || c.value >= lowest)
+                .collect()
+        };
+
+        match self {
+            DeckDefinition::French => ALL_CARDS(),
+            DeckDefinition::Piquet => from(CardValue::Seven),
+            DeckDefinition::Pinochle => {
+                let mut cards = from(CardValue::Nine);
+                cards.extend(from(CardValue::Nine));
+                cards
+            }
+            DeckDefinition::Euchre => from(CardValue::Nine),
+            DeckDefinition::Custom(codes) => codes.iter().cloned().collect(),
+        }
+    }
+}
+
+// A single deck of a card type with nothing to pick between, its whole deck or a list
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase", bound = "")]
+pub enum FullDeck<C: PlayingCard> {
+    Standard,
+    // Any list of card codes, repeated codes become separate copies
+    Custom(StringCodes<C>),
+}
+
+impl<C: PlayingCard> Default for FullDeck<C> {
+    fn default() -> Self {
+        FullDeck::Standard
+    }
+}
+
+impl<C: PlayingCard> FullDeck<C> {
+    // `standard` being every face the card type has
+    pub fn faces(&self, standard: Vec<C>) -> Vec<C> {
+        match self {
+            FullDeck::Standard => standard,
+            FullDeck::Custom(codes) => codes.iter().cloned().collect(),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use super::*;
+
+// 48 card Hanafuda deck, four cards for each month of the year
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HanafudaMonth {
+    January,
+    February,
+    March,
+    April,
+    May,
+    June,
+    July,
+    August,
+    September,
+    October,
+    November,
+    December,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HanafudaKind {
+    Bright,
+    Animal,
+    Ribbon,
+    Chaff,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct HanafudaCard {
+    pub month: HanafudaMonth,
+    pub kind: HanafudaKind,
+    pub deck: usize,
+}
+
+const MONTHS: &'static [HanafudaMonth] = &[
+    HanafudaMonth::January,
+    HanafudaMonth::February,
+    HanafudaMonth::March,
+    HanafudaMonth::April,
+    HanafudaMonth::May,
+    HanafudaMonth::June,
+    HanafudaMonth::July,
+    HanafudaMonth::August,
+    HanafudaMonth::September,
+    HanafudaMonth::October,
+    HanafudaMonth::November,
+    HanafudaMonth::December,
+];
+
+impl HanafudaMonth {
+    // The four cards of each month, chaff cards repeat
+    fn kinds(&self) -> Vec<HanafudaKind> {
+        use self::HanafudaKind::*;
+
+        match self {
+            HanafudaMonth::January => vec![Bright, Ribbon, Chaff, Chaff],
+            HanafudaMonth::February => vec![Animal, Ribbon, Chaff, Chaff],
+            HanafudaMonth::March => vec![Bright, Ribbon, Chaff, Chaff],
+            HanafudaMonth::April => vec![Animal, Ribbon, Chaff, Chaff],
+            HanafudaMonth::May => vec![Animal, Ribbon, Chaff, Chaff],
+            HanafudaMonth::June => vec![Animal, Ribbon, Chaff, Chaff],
+            HanafudaMonth::July => vec![Animal, Ribbon, Chaff, Chaff],
+            HanafudaMonth::August => vec![Bright, Animal, Chaff, Chaff],
+            HanafudaMonth::September => vec![Animal, Ribbon, Chaff, Chaff],
+            HanafudaMonth::October => vec![Animal, Ribbon, Chaff, Chaff],
+            HanafudaMonth::November => vec![Bright, Animal, Ribbon, Chaff],
+            HanafudaMonth::December => vec![Bright, Chaff, Chaff, Chaff],
+        }
+    }
+}
+
+impl HasStringCode for HanafudaMonth {
+    // Months are always two digits, "01" to "12"
+    fn from_str(s: String) -> Option<HanafudaMonth> {
+        if s.len() != 2 || !s.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        match s.parse::<usize>().ok()? {
+            n if n >= 1 && n <= MONTHS.len() => Some(MONTHS[n - 1].clone()),
+            _ => None,
+        }
+    }
+
+    fn to_str(&self) -> String {
+        let n = MONTHS.iter().position(|m| m == self).unwrap() + 1;
+        format!("{:02}", n)
+    }
+}
+
+impl HasStringCode for HanafudaKind {
+    fn from_str(s: String) -> Option<HanafudaKind> {
+        match s.as_str() {
+            "B" => Some(HanafudaKind::Bright),
+            "A" => Some(HanafudaKind::Animal),
+            "R" => Some(HanafudaKind::Ribbon),
+            "C" => Some(HanafudaKind::Chaff),
+            _ => None,
+        }
+    }
+
+    fn to_str(&self) -> String {
+        let s = match self {
+            HanafudaKind::Bright => "B",
+            HanafudaKind::Animal => "A",
+            HanafudaKind::Ribbon => "R",
+            HanafudaKind::Chaff => "C",
+        };
+        s.to_string()
+    }
+}
+
+impl HasStringCode for HanafudaCard {
+    // Kind then month, e.g "B01" is the January bright
+    fn to_str(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.kind.to_str(),
+            self.month.to_str(),
+            deck_suffix(self.deck)
+        )
+    }
+
+    fn from_str(code: String) -> Option<HanafudaCard> {
+        if code.len() < 3 || !code.is_char_boundary(1) || !code.is_char_boundary(3) {
+            return None;
+        }
+
+        let kind = HanafudaKind::from_str(code[..1].to_string())?;
+        let month = HanafudaMonth::from_str(code[1..3].to_string())?;
+        let deck = parse_deck(&code[3..])?;
+
+        // Only the kinds that month actually has
+        if month.kinds().contains(&kind) {
+            Some(HanafudaCard { month, kind, deck })
+        } else {
+            None
+        }
+    }
+}
+
+impl PlayingCard for HanafudaCard {
+    type Suit = HanafudaMonth;
+    type Value = HanafudaKind;
+    type Ranking = Points<HanafudaCard>;
+    type Definition = FullDeck<HanafudaCard>;
+
+    const TYPE: CardType = CardType::Hanafuda;
+
+    fn suit(&self) -> Option<&HanafudaMonth> {
+        Some(&self.month)
+    }
+
+    fn value(&self) -> &HanafudaKind {
+        &self.kind
+    }
+
+    fn deck(&self) -> usize {
+        self.deck
+    }
+
+    fn with_deck(&self, deck: usize) -> HanafudaCard {
+        HanafudaCard {
+            deck,
+            ..self.clone()
+        }
+    }
+
+    fn faces(definition: &FullDeck<HanafudaCard>) -> Vec<HanafudaCard> {
+        let cards = MONTHS
+            .iter()
+            .flat_map(|month| {
+                month.kinds().into_iter().map(move |kind| HanafudaCard {
+                    month: month.clone(),
+                    kind,
+                    deck: 0,
+                })
+            })
+            .collect();
+        definition.faces(cards)
+    }
+}
+
+impl CardRanking<HanafudaCard> for Points<HanafudaCard> {
+    fn rank(&self, kind: &HanafudaKind) -> u32 {
+        match kind {
+            HanafudaKind::Chaff => 1,
+            HanafudaKind::Ribbon => 2,
+            HanafudaKind::Animal => 3,
+            HanafudaKind::Bright => 4,
+        }
+    }
+
+    // The usual koi-koi card values
+    fn points(&self, card: &HanafudaCard) -> u32 {
+        if let Some(points) = self.points.get(&card.kind) {
+            return *points;
+        }
+
+        match card.kind {
+            HanafudaKind::Bright => 20,
+            HanafudaKind::Animal => 10,
+            HanafudaKind::Ribbon => 5,
+            HanafudaKind::Chaff => 1,
+        }
+    }
+}
+
+impl std::fmt::Debug for HanafudaCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+string_code_serde!(HanafudaMonth);
+string_code_serde!(HanafudaKind);
+string_code_serde!(HanafudaCard);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(code: &str) -> HanafudaCard {
+        HanafudaCard::from_str(code.to_string()).unwrap()
+    }
+
+    #[test]
+    fn codes_round_trip_and_months_only_have_their_kinds() {
+        for code in &["B01", "R02", "A08", "C12", "C121"] {
+            assert_eq!(card(code).to_str(), *code);
+        }
+        // January has no animal, December only one bright
+        for code in &["A01", "R12", "B13", "B1", "C00"] {
+            assert!(
+                HanafudaCard::from_str(code.to_string()).is_none(),
+                "{}",
+                code
+            );
+        }
+    }
+
+    #[test]
+    fn a_deck_is_four_cards_a_month() {
+        let faces = HanafudaCard::faces(&FullDeck::Standard);
+        assert_eq!(faces.len(), 48);
+        let ranking = Points::default();
+        assert_eq!(ranking.points(&card("B11")), 20);
+        assert_eq!(ranking.points(&card("C11")), 1);
+    }
+}
@@ -1,11 +1,58 @@
+// Serializes a HasStringCode type as its string code
+macro_rules! string_code_serde {
+    ($t:ty) => {
+        impl serde::Serialize for $t {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_str(self.to_str().as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $t {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deserializer.deserialize_str(CodeVisitor::<$t>::new())
+            }
+        }
+    };
+}
+
+mod playingcard;
+pub use playingcard::{deck_suffix, number_copies, parse_deck, PlayingCard};
+
+mod cardtype;
+pub use cardtype::CardType;
+
 mod card;
 pub use card::Card;
 
+mod cardranking;
+pub use cardranking::{CardRanking, PictureValues, Points, Valuation};
+
 mod cardcollection;
 pub use cardcollection::CardCollection;
 
 mod cardselection;
-pub use cardselection::CardSelection;
+pub use cardselection::{CardSelection, SelectionKind, SelectionQuery};
+
+mod orientation;
+pub use orientation::{Orientation, HIDDEN_CARD};
+
+mod placement;
+pub use placement::Placement;
+
+mod shuffle;
+pub use shuffle::ShuffleMethod;
+
+mod order;
+pub use order::{PileOrder, SortBy};
+
+mod deckdefinition;
+pub use deckdefinition::{DeckDefinition, FullDeck};
 
 mod cardsuit;
 pub use cardsuit::CardSuit;
@@ -13,6 +60,15 @@ pub use cardsuit::CardSuit;
 mod cardvalue;
 pub use cardvalue::CardValue;
 
+mod tarot;
+pub use tarot::{TarotCard, TarotSuit, TarotValue};
+
+mod uno;
+pub use uno::{UnoCard, UnoColour, UnoValue};
+
+mod hanafuda;
+pub use hanafuda::{HanafudaCard, HanafudaKind, HanafudaMonth};
+
 use super::api::CardAPIError;
 use super::stringcode::*;
 
@@ -23,6 +79,8 @@ const CARD_CODES: &'static [&str] = &[
     "0H", "JH", "QH", "KH",
 ];
 
+const JOKER_CODES: &'static [&str] = &["XR", "XB"];
+
 pub const ALL_CARDS: &'static Fn() -> Vec<Card> = &|| {
     CARD_CODES
         .iter()
@@ -30,3 +88,11 @@ pub const ALL_CARDS: &'static Fn() -> Vec<Card> = &|| {
         .map(|code| Card::from_str(code.to_string()).unwrap())
         .collect()
 };
+
+pub const ALL_JOKERS: &'static Fn() -> Vec<Card> = &|| {
+    JOKER_CODES
+        .iter()
+        .cloned()
+        .map(|code| Card::from_str(code.to_string()).unwrap())
+        .collect()
+};
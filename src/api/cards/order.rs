@@ -0,0 +1,28 @@
+use super::*;
+
+use serde::{Deserialize, Serialize};
+
+// Ways of rearranging a pile that leave nothing to chance
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "order", rename_all = "lowercase")]
+pub enum PileOrder {
+    // The top card ends up on the bottom
+    Reverse,
+    // Lowest on the bottom and highest on top
+    Sort { by: SortBy },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    Suit,
+    Value,
+    // Grouped by suit, then by value within each suit
+    Both,
+}
+
+impl Default for SortBy {
+    fn default() -> Self {
+        SortBy::Both
+    }
+}
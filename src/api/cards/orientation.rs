@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+// Which way up the cards in a pile lie
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    Up,
+    // Shown as HIDDEN_CARD, except to the player owning the pile
+    Down,
+}
+
+// What a face down card shows instead of its code
+pub const HIDDEN_CARD: &str = "??";
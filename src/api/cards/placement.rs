@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+// Where drawn cards go in the pile they're drawn onto
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    Top,
+    // Buried under the rest of the pile
+    Bottom,
+    // Each card slipped in at its own random position
+    Random,
+    // Counted down from the top, so 0 is the same as the top
+    Index(usize),
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement::Top
+    }
+}
@@ -0,0 +1,87 @@
+use super::*;
+
+use super::super::RuleSet;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+// Anything the pile, selection and persistence machinery can hold. Each card
+// type brings its own string codes, which are also how it is serialized
+pub trait PlayingCard:
+    HasStringCode + Clone + Eq + Hash + Debug + Serialize + DeserializeOwned
+{
+    type Suit: HasStringCode + Clone + Eq + Ord + Debug + Serialize + DeserializeOwned;
+    type Value: HasStringCode + Clone + Eq + Ord + Hash + Debug + Serialize + DeserializeOwned;
+    // How a game's rules rank and score these cards
+    type Ranking: CardRanking<Self> + Clone + Debug + Default + Serialize + DeserializeOwned;
+    // Which of these cards a game's rules make a single deck from
+    type Definition: Clone + Debug + Default + Serialize + DeserializeOwned;
+
+    // What a game's rules call this kind of card
+    const TYPE: CardType;
+
+    // None for cards without a suit, e.g jokers
+    fn suit(&self) -> Option<&Self::Suit>;
+    fn value(&self) -> &Self::Value;
+
+    // Which copy of this face the card is, so identical faces stay distinct
+    fn deck(&self) -> usize;
+    fn with_deck(&self, deck: usize) -> Self;
+
+    // The faces making up a single deck, possibly with repeats
+    fn faces(definition: &Self::Definition) -> Vec<Self>;
+
+    // Cards a deck can have added on top of its definition, e.g jokers
+    fn jokers() -> Vec<Self> {
+        Vec::new()
+    }
+
+    // "red" or "black" for cards that have a colour
+    fn colour(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn same_face(&self, other: &Self) -> bool {
+        self.suit() == other.suit() && self.value() == other.value()
+    }
+
+    // Every card a new game under these rules starts with
+    fn deck_for(rules: &RuleSet<Self>) -> Vec<Self> {
+        let mut deck = Self::faces(&rules.definition);
+        deck.extend(Self::jokers().into_iter().take(rules.jokers));
+
+        number_copies((0..rules.decks).flat_map(|_| deck.clone()).collect())
+    }
+}
+
+// Copies after the first carry their deck index on the end of their code, e.g "AS1"
+pub fn deck_suffix(deck: usize) -> String {
+    if deck == 0 {
+        String::new()
+    } else {
+        deck.to_string()
+    }
+}
+
+pub fn parse_deck(index: &str) -> Option<usize> {
+    if index.is_empty() {
+        Some(0)
+    } else if index.starts_with('0') || !index.chars().all(|c| c.is_ascii_digit()) {
+        None
+    } else {
+        index.parse::<usize>().ok()
+    }
+}
+
+// Gives every repeated face its own deck index so each physical card can be told apart
+pub fn number_copies<C: PlayingCard>(faces: Vec<C>) -> Vec<C> {
+    let mut cards: Vec<C> = Vec::new();
+    for card in faces {
+        let copies = cards.iter().filter(|c| c.same_face(&card)).count();
+        cards.push(card.with_deck(copies));
+    }
+    cards
+}
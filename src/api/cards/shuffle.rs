@@ -0,0 +1,201 @@
+use super::*;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+// Ways of shuffling a pile by hand. The top of a pile is the end of its cards
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "method", rename_all = "lowercase")]
+pub enum ShuffleMethod {
+    // Gilbert-Shannon-Reeds riffles, 7 is about enough to mix a 52 card deck
+    Riffle {
+        #[serde(default = "default_riffles")]
+        count: usize,
+    },
+    // Small packets taken off the top and dropped onto a new pile, `count` times over
+    Overhand {
+        #[serde(default = "default_overhands")]
+        count: usize,
+    },
+    // The top `position` cards go to the bottom, a random position if not given
+    Cut {
+        #[serde(default)]
+        position: Option<usize>,
+    },
+    // Cards spread out and mixed on the table, uniformly random
+    Wash,
+}
+
+fn default_riffles() -> usize {
+    7
+}
+
+fn default_overhands() -> usize {
+    10
+}
+
+// Chance of a packet ending after any card in an overhand shuffle
+const OVERHAND_BREAK: f64 = 0.25;
+
+impl ShuffleMethod {
+    pub fn apply<T, R: Rng + ?Sized>(
+        &self,
+        cards: &mut Vec<T>,
+        rng: &mut R,
+    ) -> Result<(), CardAPIError> {
+        match self {
+            ShuffleMethod::Riffle { count } => {
+                for _ in 0..*count {
+                    riffle(cards, rng);
+                }
+            }
+            ShuffleMethod::Overhand { count } => {
+                for _ in 0..*count {
+                    overhand(cards, rng);
+                }
+            }
+            ShuffleMethod::Cut { position } => {
+                let position = match position {
+                    Some(position) if *position > cards.len() => {
+                        return Err(CardAPIError::InvalidSelection(format!(
+                            "can't cut at {} in a pile of {}",
+                            position,
+                            cards.len()
+                        )))
+                    }
+                    Some(position) => *position,
+                    None => rng.gen_range(0, cards.len() + 1),
+                };
+                cards.rotate_right(position);
+            }
+            ShuffleMethod::Wash => cards.shuffle(rng),
+        }
+        Ok(())
+    }
+}
+
+// Cuts the pile binomially, then drops cards from the bottom of either half with a
+// chance proportional to the size of that half
+fn riffle<T, R: Rng + ?Sized>(cards: &mut Vec<T>, rng: &mut R) {
+    let top = (0..cards.len()).filter(|_| rng.gen::<bool>()).count();
+    let mut upper: Vec<T> = cards.split_off(cards.len() - top);
+    let mut lower: Vec<T> = cards.split_off(0);
+    upper.reverse();
+    lower.reverse();
+
+    while !upper.is_empty() || !lower.is_empty() {
+        let from_upper = rng.gen_range(0, upper.len() + lower.len()) < upper.len();
+        let card = if from_upper { upper.pop() } else { lower.pop() };
+        cards.extend(card);
+    }
+}
+
+// Splits the pile into packets, which land in the reverse order with each packet intact
+fn overhand<T, R: Rng + ?Sized>(cards: &mut Vec<T>, rng: &mut R) {
+    let mut packets: Vec<Vec<T>> = Vec::new();
+    let mut packet = Vec::new();
+    for card in cards.drain(..) {
+        packet.push(card);
+        if rng.gen_bool(OVERHAND_BREAK) {
+            packets.push(std::mem::replace(&mut packet, Vec::new()));
+        }
+    }
+    packets.push(packet);
+
+    cards.extend(packets.into_iter().rev().flatten());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn shuffled(method: ShuffleMethod, seed: u8) -> Vec<usize> {
+        let mut cards: Vec<usize> = (0..52).collect();
+        method
+            .apply(&mut cards, &mut StdRng::from_seed([seed; 32]))
+            .unwrap();
+        cards
+    }
+
+    fn methods() -> Vec<ShuffleMethod> {
+        vec![
+            ShuffleMethod::Riffle { count: 7 },
+            ShuffleMethod::Overhand { count: 10 },
+            ShuffleMethod::Cut { position: None },
+            ShuffleMethod::Wash,
+        ]
+    }
+
+    #[test]
+    fn every_method_keeps_the_same_cards() {
+        for method in methods() {
+            let mut cards = shuffled(method.clone(), 1);
+            cards.sort();
+            assert_eq!(cards, (0..52).collect::<Vec<usize>>(), "{:?}", method);
+        }
+    }
+
+    #[test]
+    fn every_method_follows_the_rng() {
+        for method in methods() {
+            assert_eq!(
+                shuffled(method.clone(), 1),
+                shuffled(method.clone(), 1),
+                "{:?}",
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn one_riffle_interleaves_two_halves() {
+        let cards = shuffled(ShuffleMethod::Riffle { count: 1 }, 2);
+        // Each half keeps its order, so a card can only come after the one above it once,
+        // where the halves meet
+        let position = |card| cards.iter().position(|c| *c == card).unwrap();
+        let breaks = (0..51)
+            .filter(|card| position(card + 1) < position(*card))
+            .count();
+        assert!(breaks <= 1);
+        assert_ne!(cards, (0..52).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn one_overhand_reverses_packets() {
+        let cards = shuffled(ShuffleMethod::Overhand { count: 1 }, 3);
+        for pair in cards.windows(2) {
+            assert!(pair[1] == pair[0] + 1 || pair[1] < pair[0], "{:?}", pair);
+        }
+        assert_ne!(cards, (0..52).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn cut_moves_the_top_cards_to_the_bottom() {
+        let mut cards: Vec<usize> = (0..5).collect();
+        let mut rng = StdRng::from_seed([0; 32]);
+        ShuffleMethod::Cut { position: Some(2) }
+            .apply(&mut cards, &mut rng)
+            .unwrap();
+        assert_eq!(cards, vec![3, 4, 0, 1, 2]);
+
+        match (ShuffleMethod::Cut { position: Some(6) }).apply(&mut cards, &mut rng) {
+            Err(CardAPIError::InvalidSelection(_)) => {}
+            other => panic!("expected InvalidSelection, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn wash_mixes_the_whole_pile() {
+        assert_ne!(
+            shuffled(ShuffleMethod::Wash, 4),
+            (0..52).collect::<Vec<usize>>()
+        );
+        assert_ne!(
+            shuffled(ShuffleMethod::Wash, 4),
+            shuffled(ShuffleMethod::Wash, 5)
+        );
+    }
+}
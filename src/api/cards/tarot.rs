@@ -0,0 +1,292 @@
+use super::*;
+
+// 78 card tarot: four suits of 14 plus the Fool and 21 trumps
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TarotSuit {
+    Wands,
+    Cups,
+    Swords,
+    Pentacles,
+    Trumps,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TarotValue {
+    Ace,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Page,
+    Knight,
+    Queen,
+    King,
+    // 0 is the Fool, 1 to 21 are the trumps
+    Trump(u8),
+}
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct TarotCard {
+    pub suit: TarotSuit,
+    pub value: TarotValue,
+    pub deck: usize,
+}
+
+impl HasStringCode for TarotSuit {
+    fn from_str(s: String) -> Option<TarotSuit> {
+        match s.as_str() {
+            "W" => Some(TarotSuit::Wands),
+            "C" => Some(TarotSuit::Cups),
+            "S" => Some(TarotSuit::Swords),
+            "P" => Some(TarotSuit::Pentacles),
+            "T" => Some(TarotSuit::Trumps),
+            _ => None,
+        }
+    }
+
+    fn to_str(&self) -> String {
+        let s = match self {
+            TarotSuit::Wands => "W",
+            TarotSuit::Cups => "C",
+            TarotSuit::Swords => "S",
+            TarotSuit::Pentacles => "P",
+            TarotSuit::Trumps => "T",
+        };
+        s.to_string()
+    }
+}
+
+impl HasStringCode for TarotValue {
+    // Trumps are always two digits, "00" to "21"
+    fn to_str(&self) -> String {
+        let s = match self {
+            TarotValue::Ace => "A",
+            TarotValue::Two => "2",
+            TarotValue::Three => "3",
+            TarotValue::Four => "4",
+            TarotValue::Five => "5",
+            TarotValue::Six => "6",
+            TarotValue::Seven => "7",
+            TarotValue::Eight => "8",
+            TarotValue::Nine => "9",
+            TarotValue::Ten => "0",
+            TarotValue::Page => "P",
+            TarotValue::Knight => "N",
+            TarotValue::Queen => "Q",
+            TarotValue::King => "K",
+            TarotValue::Trump(n) => return format!("{:02}", n),
+        };
+        s.to_string()
+    }
+
+    fn from_str(s: String) -> Option<TarotValue> {
+        match s.as_str() {
+            "A" => Some(TarotValue::Ace),
+            "2" => Some(TarotValue::Two),
+            "3" => Some(TarotValue::Three),
+            "4" => Some(TarotValue::Four),
+            "5" => Some(TarotValue::Five),
+            "6" => Some(TarotValue::Six),
+            "7" => Some(TarotValue::Seven),
+            "8" => Some(TarotValue::Eight),
+            "9" => Some(TarotValue::Nine),
+            "0" => Some(TarotValue::Ten),
+            "P" => Some(TarotValue::Page),
+            "N" => Some(TarotValue::Knight),
+            "Q" => Some(TarotValue::Queen),
+            "K" => Some(TarotValue::King),
+            n if n.len() == 2 && n.chars().all(|c| c.is_ascii_digit()) => {
+                match n.parse::<u8>().ok()? {
+                    n if n <= 21 => Some(TarotValue::Trump(n)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl HasStringCode for TarotCard {
+    fn to_str(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.value.to_str(),
+            self.suit.to_str(),
+            deck_suffix(self.deck)
+        )
+    }
+
+    fn from_str(code: String) -> Option<TarotCard> {
+        let digits = code.chars().take_while(|c| c.is_ascii_digit()).count();
+        let split = if digits >= 2 { 2 } else { 1 };
+        if code.len() < split + 1 || !code.is_char_boundary(split + 1) {
+            return None;
+        }
+
+        let value = TarotValue::from_str(code[..split].to_string())?;
+        let suit = TarotSuit::from_str(code[split..split + 1].to_string())?;
+        let deck = parse_deck(&code[split + 1..])?;
+
+        // Trumps only belong to the trump suit, and the trump suit only holds trumps
+        match (&value, &suit) {
+            (TarotValue::Trump(_), TarotSuit::Trumps) => Some(TarotCard { suit, value, deck }),
+            (TarotValue::Trump(_), _) | (_, TarotSuit::Trumps) => None,
+            _ => Some(TarotCard { suit, value, deck }),
+        }
+    }
+}
+
+impl PlayingCard for TarotCard {
+    type Suit = TarotSuit;
+    type Value = TarotValue;
+    type Ranking = Points<TarotCard>;
+    type Definition = FullDeck<TarotCard>;
+
+    const TYPE: CardType = CardType::Tarot;
+
+    fn suit(&self) -> Option<&TarotSuit> {
+        Some(&self.suit)
+    }
+
+    fn value(&self) -> &TarotValue {
+        &self.value
+    }
+
+    fn deck(&self) -> usize {
+        self.deck
+    }
+
+    fn with_deck(&self, deck: usize) -> TarotCard {
+        TarotCard {
+            deck,
+            ..self.clone()
+        }
+    }
+
+    fn faces(definition: &FullDeck<TarotCard>) -> Vec<TarotCard> {
+        let suits = vec![
+            TarotSuit::Wands,
+            TarotSuit::Cups,
+            TarotSuit::Swords,
+            TarotSuit::Pentacles,
+        ];
+        let values = vec![
+            TarotValue::Ace,
+            TarotValue::Two,
+            TarotValue::Three,
+            TarotValue::Four,
+            TarotValue::Five,
+            TarotValue::Six,
+            TarotValue::Seven,
+            TarotValue::Eight,
+            TarotValue::Nine,
+            TarotValue::Ten,
+            TarotValue::Page,
+            TarotValue::Knight,
+            TarotValue::Queen,
+            TarotValue::King,
+        ];
+
+        let mut cards: Vec<TarotCard> = suits
+            .iter()
+            .flat_map(|suit| {
+                values.iter().map(move |value| TarotCard {
+                    suit: suit.clone(),
+                    value: value.clone(),
+                    deck: 0,
+                })
+            })
+            .collect();
+        cards.extend((0..22).map(|n| TarotCard {
+            suit: TarotSuit::Trumps,
+            value: TarotValue::Trump(n),
+            deck: 0,
+        }));
+        definition.faces(cards)
+    }
+}
+
+impl CardRanking<TarotCard> for Points<TarotCard> {
+    // Trumps above every suit, the Fool lowest of them
+    fn rank(&self, value: &TarotValue) -> u32 {
+        match value {
+            TarotValue::Ace => 1,
+            TarotValue::Two => 2,
+            TarotValue::Three => 3,
+            TarotValue::Four => 4,
+            TarotValue::Five => 5,
+            TarotValue::Six => 6,
+            TarotValue::Seven => 7,
+            TarotValue::Eight => 8,
+            TarotValue::Nine => 9,
+            TarotValue::Ten => 10,
+            TarotValue::Page => 11,
+            TarotValue::Knight => 12,
+            TarotValue::Queen => 13,
+            TarotValue::King => 14,
+            TarotValue::Trump(n) => 15 + *n as u32,
+        }
+    }
+
+    // Counted in half points, so the whole deck is worth 182. The Fool, 1 and 21 count
+    // as much as a king
+    fn points(&self, card: &TarotCard) -> u32 {
+        if let Some(points) = self.points.get(&card.value) {
+            return *points;
+        }
+
+        match card.value {
+            TarotValue::King
+            | TarotValue::Trump(0)
+            | TarotValue::Trump(1)
+            | TarotValue::Trump(21) => 9,
+            TarotValue::Queen => 7,
+            TarotValue::Knight => 5,
+            TarotValue::Page => 3,
+            _ => 1,
+        }
+    }
+}
+
+impl std::fmt::Debug for TarotCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+string_code_serde!(TarotSuit);
+string_code_serde!(TarotValue);
+string_code_serde!(TarotCard);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(code: &str) -> TarotCard {
+        TarotCard::from_str(code.to_string()).unwrap()
+    }
+
+    #[test]
+    fn codes_round_trip_and_trumps_only_go_with_their_suit() {
+        for code in &["AW", "0C", "NS", "KP", "00T", "21T", "07T1"] {
+            assert_eq!(card(code).to_str(), *code);
+        }
+        for code in &["22T", "7T", "AT", "05W", "XW", "AW0"] {
+            assert!(TarotCard::from_str(code.to_string()).is_none(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn a_deck_is_78_cards_worth_182_half_points() {
+        let faces = TarotCard::faces(&FullDeck::Standard);
+        assert_eq!(faces.len(), 78);
+        let ranking = Points::default();
+        assert_eq!(faces.iter().map(|c| ranking.points(c)).sum::<u32>(), 182);
+        assert!(ranking.compare(&card("00T"), &card("KW")) == std::cmp::Ordering::Greater);
+    }
+}
@@ -0,0 +1,254 @@
+use super::*;
+
+// 108 card Uno deck, wild cards have their own colour
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnoColour {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Wild,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnoValue {
+    Number(u8),
+    Skip,
+    Reverse,
+    DrawTwo,
+    Wild,
+    WildDrawFour,
+}
+
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct UnoCard {
+    pub colour: UnoColour,
+    pub value: UnoValue,
+    pub deck: usize,
+}
+
+impl UnoValue {
+    pub fn is_wild(&self) -> bool {
+        *self == UnoValue::Wild || *self == UnoValue::WildDrawFour
+    }
+}
+
+impl HasStringCode for UnoColour {
+    fn from_str(s: String) -> Option<UnoColour> {
+        match s.as_str() {
+            "R" => Some(UnoColour::Red),
+            "Y" => Some(UnoColour::Yellow),
+            "G" => Some(UnoColour::Green),
+            "B" => Some(UnoColour::Blue),
+            "W" => Some(UnoColour::Wild),
+            _ => None,
+        }
+    }
+
+    fn to_str(&self) -> String {
+        let s = match self {
+            UnoColour::Red => "R",
+            UnoColour::Yellow => "Y",
+            UnoColour::Green => "G",
+            UnoColour::Blue => "B",
+            UnoColour::Wild => "W",
+        };
+        s.to_string()
+    }
+}
+
+impl HasStringCode for UnoValue {
+    fn to_str(&self) -> String {
+        let s = match self {
+            UnoValue::Number(n) => return n.to_string(),
+            UnoValue::Skip => "S",
+            UnoValue::Reverse => "R",
+            UnoValue::DrawTwo => "D",
+            UnoValue::Wild => "W",
+            UnoValue::WildDrawFour => "F",
+        };
+        s.to_string()
+    }
+
+    fn from_str(s: String) -> Option<UnoValue> {
+        match s.as_str() {
+            "S" => Some(UnoValue::Skip),
+            "R" => Some(UnoValue::Reverse),
+            "D" => Some(UnoValue::DrawTwo),
+            "W" => Some(UnoValue::Wild),
+            "F" => Some(UnoValue::WildDrawFour),
+            n if n.len() == 1 && n.chars().all(|c| c.is_ascii_digit()) => {
+                Some(UnoValue::Number(n.parse::<u8>().ok()?))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl HasStringCode for UnoCard {
+    fn to_str(&self) -> String {
+        format!(
+            "{}{}{}",
+            self.value.to_str(),
+            self.colour.to_str(),
+            deck_suffix(self.deck)
+        )
+    }
+
+    fn from_str(code: String) -> Option<UnoCard> {
+        let mut chars = code.chars();
+        let value = UnoValue::from_str(chars.next()?.to_string())?;
+        let colour = UnoColour::from_str(chars.next()?.to_string())?;
+        let deck = parse_deck(chars.as_str())?;
+
+        // Wild cards are the only cards without a real colour
+        if value.is_wild() == (colour == UnoColour::Wild) {
+            Some(UnoCard {
+                colour,
+                value,
+                deck,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl PlayingCard for UnoCard {
+    type Suit = UnoColour;
+    type Value = UnoValue;
+    type Ranking = Points<UnoCard>;
+    type Definition = FullDeck<UnoCard>;
+
+    const TYPE: CardType = CardType::Uno;
+
+    fn suit(&self) -> Option<&UnoColour> {
+        Some(&self.colour)
+    }
+
+    fn value(&self) -> &UnoValue {
+        &self.value
+    }
+
+    fn deck(&self) -> usize {
+        self.deck
+    }
+
+    fn with_deck(&self, deck: usize) -> UnoCard {
+        UnoCard {
+            deck,
+            ..self.clone()
+        }
+    }
+
+    // One 0 and two of everything else per colour, plus four of each wild
+    fn faces(definition: &FullDeck<UnoCard>) -> Vec<UnoCard> {
+        let card = |colour: &UnoColour, value: UnoValue| UnoCard {
+            colour: colour.clone(),
+            value,
+            deck: 0,
+        };
+
+        let mut cards = Vec::new();
+        for colour in &[
+            UnoColour::Red,
+            UnoColour::Yellow,
+            UnoColour::Green,
+            UnoColour::Blue,
+        ] {
+            cards.push(card(colour, UnoValue::Number(0)));
+            for _ in 0..2 {
+                cards.extend((1..10).map(|n| card(colour, UnoValue::Number(n))));
+                cards.push(card(colour, UnoValue::Skip));
+                cards.push(card(colour, UnoValue::Reverse));
+                cards.push(card(colour, UnoValue::DrawTwo));
+            }
+        }
+        for _ in 0..4 {
+            cards.push(card(&UnoColour::Wild, UnoValue::Wild));
+            cards.push(card(&UnoColour::Wild, UnoValue::WildDrawFour));
+        }
+        definition.faces(cards)
+    }
+
+    // Wild cards have none
+    fn colour(&self) -> Option<&'static str> {
+        match self.colour {
+            UnoColour::Red => Some("red"),
+            UnoColour::Yellow => Some("yellow"),
+            UnoColour::Green => Some("green"),
+            UnoColour::Blue => Some("blue"),
+            UnoColour::Wild => None,
+        }
+    }
+}
+
+impl CardRanking<UnoCard> for Points<UnoCard> {
+    fn rank(&self, value: &UnoValue) -> u32 {
+        match value {
+            UnoValue::Number(n) => *n as u32,
+            UnoValue::Skip => 10,
+            UnoValue::Reverse => 11,
+            UnoValue::DrawTwo => 12,
+            UnoValue::Wild => 13,
+            UnoValue::WildDrawFour => 14,
+        }
+    }
+
+    // What a card left in hand costs at the end of a round
+    fn points(&self, card: &UnoCard) -> u32 {
+        if let Some(points) = self.points.get(&card.value) {
+            return *points;
+        }
+
+        match card.value {
+            UnoValue::Number(n) => n as u32,
+            UnoValue::Skip | UnoValue::Reverse | UnoValue::DrawTwo => 20,
+            UnoValue::Wild | UnoValue::WildDrawFour => 50,
+        }
+    }
+}
+
+impl std::fmt::Debug for UnoCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+string_code_serde!(UnoColour);
+string_code_serde!(UnoValue);
+string_code_serde!(UnoCard);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(code: &str) -> UnoCard {
+        UnoCard::from_str(code.to_string()).unwrap()
+    }
+
+    #[test]
+    fn codes_round_trip_and_only_wild_cards_are_wild() {
+        for code in &["0R", "9B", "SY", "RG", "DB1", "WW", "FW3"] {
+            assert_eq!(card(code).to_str(), *code);
+        }
+        for code in &["WR", "5W", "10R", "SX", "R"] {
+            assert!(UnoCard::from_str(code.to_string()).is_none(), "{}", code);
+        }
+    }
+
+    #[test]
+    fn a_deck_is_108_cards_with_their_copies_numbered() {
+        let deck = number_copies(UnoCard::faces(&FullDeck::Standard));
+        assert_eq!(deck.len(), 108);
+        assert!(deck.contains(&card("5R1")));
+        assert!(!deck.contains(&card("0R1")));
+        assert!(deck.contains(&card("FW3")));
+
+        let ranking = Points::default();
+        assert_eq!(ranking.points(&card("7G")), 7);
+        assert_eq!(ranking.points(&card("DB")), 20);
+        assert_eq!(ranking.points(&card("FW")), 50);
+        assert_eq!(card("WW").colour(), None);
+    }
+}
@@ -2,27 +2,153 @@
 pub enum CardAPIError {
     DieselError(diesel::result::Error),
     NotFound(String),
-    AlreadyExists,
+    AlreadyExists(String),
     NotEnoughCards,
     CardNotInCollection,
     CardAlreadyInCollection,
+    InvalidRules(String),
+    InvalidSelection(String),
+    InvalidBody(String),
+    MoveFailed(usize, Box<CardAPIError>),
+    NothingToUndo,
+    NothingToRedo,
+    NotRevealed,
+    GameFinished,
+    // Seats that still have to give their seed
+    AwaitingSeeds(Vec<usize>),
+    SeedNotAccepted(String),
+    GameFull,
+    NotYourTurn,
+    // The most cards the pile can hold
+    PileFull(usize),
+    SelectionNotAllowed(String),
+    MoveNotAllowed(String),
+    Unauthorized,
+    Forbidden(String),
+    // The version the game is at now, and its current state
+    VersionConflict(i32, JsonValue),
+    PreconditionFailed(i32, JsonValue),
 }
 
-use rocket::http::Status;
+use super::etag::etag;
+
+use rocket::http::{ContentType, Status};
 use rocket::request::Request;
 use rocket::response::{Responder, Response};
+use rocket_contrib::json::JsonValue;
 use std::io::Cursor;
 impl Responder<'static> for CardAPIError {
-    fn respond_to(self, _: &Request) -> Result<Response<'static>, Status> {
+    fn respond_to(self, req: &Request) -> Result<Response<'static>, Status> {
         match self {
             CardAPIError::NotFound(s) => Response::build()
                 .status(Status::raw(404))
                 .sized_body(Cursor::new(format!("Not found: {}", s)))
                 .ok(),
+            CardAPIError::AlreadyExists(s) => Response::build()
+                .status(Status::raw(409))
+                .sized_body(Cursor::new(format!("Already exists: {}", s)))
+                .ok(),
             CardAPIError::NotEnoughCards => Response::build()
                 .status(Status::raw(500))
                 .sized_body(Cursor::new("Not enough cards"))
                 .ok(),
+            CardAPIError::CardNotInCollection => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new("Card not in collection"))
+                .ok(),
+            CardAPIError::CardAlreadyInCollection => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new("Card already in collection"))
+                .ok(),
+            CardAPIError::InvalidRules(s) => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new(format!("Invalid rules: {}", s)))
+                .ok(),
+            CardAPIError::InvalidSelection(s) => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new(format!("Invalid selection: {}", s)))
+                .ok(),
+            CardAPIError::InvalidBody(s) => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new(format!("Invalid body: {}", s)))
+                .ok(),
+            CardAPIError::NothingToUndo => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new("Nothing to undo"))
+                .ok(),
+            CardAPIError::NothingToRedo => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new("Nothing to redo"))
+                .ok(),
+            CardAPIError::NotRevealed => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new("Seed not revealed"))
+                .ok(),
+            CardAPIError::GameFinished => Response::build()
+                .status(Status::raw(409))
+                .sized_body(Cursor::new("Game has finished"))
+                .ok(),
+            CardAPIError::AwaitingSeeds(seats) => Response::build()
+                .status(Status::raw(409))
+                .sized_body(Cursor::new(format!(
+                    "Waiting for seeds from seats {:?}",
+                    seats
+                )))
+                .ok(),
+            CardAPIError::SeedNotAccepted(s) => Response::build()
+                .status(Status::raw(409))
+                .sized_body(Cursor::new(format!("Seed not accepted: {}", s)))
+                .ok(),
+            CardAPIError::GameFull => Response::build()
+                .status(Status::raw(409))
+                .sized_body(Cursor::new("Game is full"))
+                .ok(),
+            CardAPIError::NotYourTurn => Response::build()
+                .status(Status::raw(409))
+                .sized_body(Cursor::new("Not your turn"))
+                .ok(),
+            CardAPIError::PileFull(capacity) => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new(format!(
+                    "Pile is full, it holds at most {} cards",
+                    capacity
+                )))
+                .ok(),
+            CardAPIError::SelectionNotAllowed(s) => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new(format!("Selection not allowed: {}", s)))
+                .ok(),
+            CardAPIError::MoveNotAllowed(s) => Response::build()
+                .status(Status::raw(400))
+                .sized_body(Cursor::new(format!("Move not allowed: {}", s)))
+                .ok(),
+            CardAPIError::Unauthorized => Response::build()
+                .status(Status::raw(401))
+                .raw_header("WWW-Authenticate", "Bearer")
+                .sized_body(Cursor::new("Missing or invalid token"))
+                .ok(),
+            CardAPIError::Forbidden(s) => Response::build()
+                .status(Status::raw(403))
+                .sized_body(Cursor::new(format!("Not allowed: {}", s)))
+                .ok(),
+            // Same response as the failing move, with its index in the batch
+            CardAPIError::MoveFailed(index, e) => {
+                let mut response = e.respond_to(req)?;
+                response.set_raw_header("X-Failed-Move", index.to_string());
+                Ok(response)
+            }
+            CardAPIError::VersionConflict(version, game) => Response::build()
+                .status(Status::raw(409))
+                .header(ContentType::JSON)
+                .raw_header("ETag", etag(version))
+                .sized_body(Cursor::new(game.to_string()))
+                .ok(),
+            CardAPIError::PreconditionFailed(version, game) => Response::build()
+                .status(Status::raw(412))
+                .header(ContentType::JSON)
+                .raw_header("ETag", etag(version))
+                .sized_body(Cursor::new(game.to_string()))
+                .ok(),
             CardAPIError::DieselError(e) => Response::build()
                 .status(Status::raw(500))
                 .sized_body(Cursor::new(format!("Database Error: {}", e)))
@@ -0,0 +1,56 @@
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::Outcome;
+
+// Game versions are sent as strong entity tags, e.g "3"
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+// The versions a request is willing to overwrite, None matches any version
+pub struct IfMatch(Option<Vec<i32>>);
+
+impl IfMatch {
+    pub fn matches(&self, version: i32) -> bool {
+        match &self.0 {
+            Some(versions) => versions.contains(&version),
+            None => true,
+        }
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for IfMatch {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<IfMatch, String> {
+        let header = match request.headers().get_one("If-Match") {
+            Some(header) => header.trim(),
+            None => return Outcome::Success(IfMatch(None)),
+        };
+        if header == "*" {
+            return Outcome::Success(IfMatch(None));
+        }
+
+        let versions: Option<Vec<i32>> = header
+            .split(',')
+            .map(|tag| tag.trim().trim_matches('"').parse::<i32>().ok())
+            .collect();
+
+        match versions {
+            Some(versions) => Outcome::Success(IfMatch(Some(versions))),
+            None => Outcome::Failure((Status::BadRequest, format!("Invalid If-Match: {}", header))),
+        }
+    }
+}
+
+// Adds the game version as an ETag to any response
+pub struct Tagged<R>(pub i32, pub R);
+
+impl<'r, R: Responder<'r>> Responder<'r> for Tagged<R> {
+    fn respond_to(self, req: &Request) -> response::Result<'r> {
+        Response::build_from(self.1.respond_to(req)?)
+            .raw_header("ETag", etag(self.0))
+            .ok()
+    }
+}
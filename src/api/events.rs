@@ -0,0 +1,496 @@
+use super::api::*;
+use super::cards::*;
+use super::models;
+
+use diesel::prelude::*;
+use diesel::result::Error::DeserializationError;
+
+use serde::{Deserialize, Serialize};
+
+// Everything that changes a game's piles. Replaying a game's events in order
+// from nothing rebuilds its piles
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase", bound = "")]
+pub enum GameEvent<C: PlayingCard = Card> {
+    // A pile was added holding these cards
+    Pile {
+        name: String,
+        cards: CardCollection<C>,
+    },
+    // The cards moved, where each one was in `from` and where it ended up in `to`.
+    // `turned` are the ones turned over to lie like the cards in `to`
+    Draw {
+        from: String,
+        to: String,
+        selection: CardSelection<C>,
+        cards: CardCollection<C>,
+        from_positions: Vec<usize>,
+        to_positions: Vec<usize>,
+        #[serde(default = "CardCollection::new")]
+        turned: CardCollection<C>,
+    },
+    // The pile was shuffled into the order of `cards`, each one coming from its from_position
+    Shuffle {
+        pile: String,
+        method: ShuffleMethod,
+        cards: CardCollection<C>,
+        from_positions: Vec<usize>,
+    },
+    // The pile was reversed or sorted into the order of `cards`
+    Reorder {
+        pile: String,
+        order: PileOrder,
+        cards: CardCollection<C>,
+        from_positions: Vec<usize>,
+    },
+    // The selected cards of the pile were turned over
+    Flip {
+        pile: String,
+        selection: CardSelection<C>,
+        cards: CardCollection<C>,
+    },
+    // The pile's cards were set to lie `face`, having lain `was`. `cards` had to be turned over
+    Face {
+        pile: String,
+        face: Orientation,
+        was: Orientation,
+        cards: CardCollection<C>,
+    },
+    // The pile's rules were replaced
+    Rules {
+        pile: String,
+        rules: PileRules<C>,
+    },
+    // The move at `target` was taken back, its cards moving from `from` to `to`
+    Undo {
+        target: i32,
+        from: String,
+        to: String,
+        cards: CardCollection<C>,
+    },
+    // The move at `target` was made again
+    Redo {
+        target: i32,
+        from: String,
+        to: String,
+        cards: CardCollection<C>,
+    },
+}
+
+// An event as it was stored, with its place in the game's history
+#[derive(Serialize, Clone, Debug)]
+#[serde(bound = "")]
+pub struct RecordedEvent<C: PlayingCard = Card> {
+    pub seq: i32,
+    pub at: String,
+    #[serde(flatten)]
+    pub event: GameEvent<C>,
+}
+
+// What a draw or shuffle moved, enough to put its cards back or move them again
+pub struct Movement<'a, C: PlayingCard> {
+    pub from: &'a String,
+    pub to: &'a String,
+    pub cards: &'a CardCollection<C>,
+    pub from_positions: &'a Vec<usize>,
+    pub to_positions: Vec<usize>,
+}
+
+impl<'a, C: PlayingCard> Movement<'a, C> {
+    // Draws recorded before positions were kept can't be taken back
+    pub fn is_reversible(&self) -> bool {
+        self.from_positions.len() == self.cards.remaining()
+            && self.to_positions.len() == self.cards.remaining()
+    }
+}
+
+impl<C: PlayingCard> GameEvent<C> {
+    pub fn movement(&self) -> Option<Movement<C>> {
+        match self {
+            GameEvent::Draw {
+                from,
+                to,
+                cards,
+                from_positions,
+                to_positions,
+                ..
+            } => Some(Movement {
+                from,
+                to,
+                cards,
+                from_positions,
+                to_positions: to_positions.clone(),
+            }),
+            GameEvent::Shuffle {
+                pile,
+                cards,
+                from_positions,
+                ..
+            }
+            | GameEvent::Reorder {
+                pile,
+                cards,
+                from_positions,
+                ..
+            } => Some(Movement {
+                from: pile,
+                to: pile,
+                cards,
+                from_positions,
+                to_positions: (0..cards.remaining()).collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    // The piles a move was made from and to, and the cards it moved or turned over
+    pub fn touched(&self) -> Option<(&String, &String, &CardCollection<C>)> {
+        match self {
+            GameEvent::Draw {
+                from, to, cards, ..
+            } => Some((from, to, cards)),
+            GameEvent::Shuffle { pile, cards, .. }
+            | GameEvent::Reorder { pile, cards, .. }
+            | GameEvent::Flip { pile, cards, .. }
+            | GameEvent::Face { pile, cards, .. } => Some((pile, pile, cards)),
+            _ => None,
+        }
+    }
+
+    // The cards a move turned over
+    pub fn turned(&self) -> Option<&CardCollection<C>> {
+        match self {
+            GameEvent::Draw { turned, .. } => Some(turned),
+            GameEvent::Flip { cards, .. } | GameEvent::Face { cards, .. } => Some(cards),
+            _ => None,
+        }
+    }
+
+    pub fn to_model(&self, game_id: &str, seq: i32) -> models::NewEvent {
+        let mut model = models::NewEvent {
+            game_id: game_id.to_string(),
+            seq,
+            kind: String::new(),
+            from_pile: None,
+            to_pile: None,
+            selection: None,
+            cards: String::new(),
+            from_positions: None,
+            to_positions: None,
+            target: None,
+            turned: None,
+        };
+
+        let no_cards = CardCollection::new();
+        let cards = match self {
+            GameEvent::Pile { name, cards } => {
+                model.kind = String::from("pile");
+                model.to_pile = Some(name.clone());
+                cards
+            }
+            GameEvent::Draw {
+                from,
+                to,
+                selection,
+                cards,
+                from_positions,
+                to_positions,
+                turned,
+            } => {
+                model.kind = String::from("draw");
+                model.from_pile = Some(from.clone());
+                model.to_pile = Some(to.clone());
+                model.selection = Some(serde_json::to_string(selection).unwrap());
+                model.from_positions = Some(serde_json::to_string(from_positions).unwrap());
+                model.to_positions = Some(serde_json::to_string(to_positions).unwrap());
+                model.turned = Some(serde_json::to_string(turned).unwrap());
+                cards
+            }
+            GameEvent::Shuffle {
+                pile,
+                method,
+                cards,
+                from_positions,
+            } => {
+                model.kind = String::from("shuffle");
+                model.from_pile = Some(pile.clone());
+                model.to_pile = Some(pile.clone());
+                model.selection = Some(serde_json::to_string(method).unwrap());
+                model.from_positions = Some(serde_json::to_string(from_positions).unwrap());
+                cards
+            }
+            GameEvent::Reorder {
+                pile,
+                order,
+                cards,
+                from_positions,
+            } => {
+                model.kind = String::from("reorder");
+                model.from_pile = Some(pile.clone());
+                model.to_pile = Some(pile.clone());
+                model.selection = Some(serde_json::to_string(order).unwrap());
+                model.from_positions = Some(serde_json::to_string(from_positions).unwrap());
+                cards
+            }
+            GameEvent::Flip {
+                pile,
+                selection,
+                cards,
+            } => {
+                model.kind = String::from("flip");
+                model.from_pile = Some(pile.clone());
+                model.to_pile = Some(pile.clone());
+                model.selection = Some(serde_json::to_string(selection).unwrap());
+                cards
+            }
+            GameEvent::Face {
+                pile,
+                face,
+                was,
+                cards,
+            } => {
+                model.kind = String::from("face");
+                model.from_pile = Some(pile.clone());
+                model.to_pile = Some(pile.clone());
+                model.selection = Some(
+                    serde_json::to_string(&FaceChange {
+                        face: *face,
+                        was: *was,
+                    })
+                    .unwrap(),
+                );
+                cards
+            }
+            GameEvent::Rules { pile, rules } => {
+                model.kind = String::from("rules");
+                model.to_pile = Some(pile.clone());
+                model.selection = Some(serde_json::to_string(rules).unwrap());
+                &no_cards
+            }
+            GameEvent::Undo {
+                target,
+                from,
+                to,
+                cards,
+            }
+            | GameEvent::Redo {
+                target,
+                from,
+                to,
+                cards,
+            } => {
+                model.kind = match self {
+                    GameEvent::Undo { .. } => String::from("undo"),
+                    _ => String::from("redo"),
+                };
+                model.from_pile = Some(from.clone());
+                model.to_pile = Some(to.clone());
+                model.target = Some(*target);
+                cards
+            }
+        };
+        model.cards = serde_json::to_string(cards).unwrap();
+
+        model
+    }
+
+    pub fn from_model(m: models::Event) -> QueryResult<RecordedEvent<C>> {
+        let missing = |column: &str| DeserializationError(format!("Missing {}", column).into());
+        let cards = serde_json::from_str(&m.cards).map_err(|e| DeserializationError(e.into()))?;
+
+        let event = match m.kind.as_str() {
+            "pile" => GameEvent::Pile {
+                name: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                cards,
+            },
+            // Draws recorded before positions were kept have none
+            "draw" => GameEvent::Draw {
+                from: m.from_pile.ok_or_else(|| missing("from_pile"))?,
+                to: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                selection: serde_json::from_str(&m.selection.ok_or_else(|| missing("selection"))?)
+                    .map_err(|e| DeserializationError(e.into()))?,
+                cards,
+                from_positions: parse_positions(m.from_positions)?,
+                to_positions: parse_positions(m.to_positions)?,
+                turned: match m.turned {
+                    Some(turned) => {
+                        serde_json::from_str(&turned).map_err(|e| DeserializationError(e.into()))?
+                    }
+                    None => CardCollection::new(),
+                },
+            },
+            "shuffle" => GameEvent::Shuffle {
+                pile: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                method: serde_json::from_str(&m.selection.ok_or_else(|| missing("selection"))?)
+                    .map_err(|e| DeserializationError(e.into()))?,
+                cards,
+                from_positions: parse_positions(m.from_positions)?,
+            },
+            "reorder" => GameEvent::Reorder {
+                pile: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                order: serde_json::from_str(&m.selection.ok_or_else(|| missing("selection"))?)
+                    .map_err(|e| DeserializationError(e.into()))?,
+                cards,
+                from_positions: parse_positions(m.from_positions)?,
+            },
+            "flip" => GameEvent::Flip {
+                pile: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                selection: serde_json::from_str(&m.selection.ok_or_else(|| missing("selection"))?)
+                    .map_err(|e| DeserializationError(e.into()))?,
+                cards,
+            },
+            "face" => {
+                let change: FaceChange =
+                    serde_json::from_str(&m.selection.ok_or_else(|| missing("selection"))?)
+                        .map_err(|e| DeserializationError(e.into()))?;
+                GameEvent::Face {
+                    pile: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                    face: change.face,
+                    was: change.was,
+                    cards,
+                }
+            }
+            "rules" => GameEvent::Rules {
+                pile: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                rules: serde_json::from_str(&m.selection.ok_or_else(|| missing("selection"))?)
+                    .map_err(|e| DeserializationError(e.into()))?,
+            },
+            "undo" => GameEvent::Undo {
+                target: m.target.ok_or_else(|| missing("target"))?,
+                from: m.from_pile.ok_or_else(|| missing("from_pile"))?,
+                to: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                cards,
+            },
+            "redo" => GameEvent::Redo {
+                target: m.target.ok_or_else(|| missing("target"))?,
+                from: m.from_pile.ok_or_else(|| missing("from_pile"))?,
+                to: m.to_pile.ok_or_else(|| missing("to_pile"))?,
+                cards,
+            },
+            kind => {
+                return Err(DeserializationError(
+                    format!("Unknown event {}", kind).into(),
+                ))
+            }
+        };
+
+        Ok(RecordedEvent {
+            seq: m.seq,
+            at: m.created_at,
+            event,
+        })
+    }
+}
+
+// How a face event's orientations are stored, in the selection column
+#[derive(Serialize, Deserialize)]
+struct FaceChange {
+    face: Orientation,
+    was: Orientation,
+}
+
+fn parse_positions(positions: Option<String>) -> QueryResult<Vec<usize>> {
+    match positions {
+        Some(positions) => {
+            serde_json::from_str(&positions).map_err(|e| DeserializationError(e.into()))
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+// The seqs of the moves that can be undone and redone, most recent last. Nothing before
+// a move that can't be taken back can be either
+pub fn move_stacks<C: PlayingCard>(history: &Vec<RecordedEvent<C>>) -> (Vec<i32>, Vec<i32>) {
+    let mut done = Vec::new();
+    let mut undone = Vec::new();
+
+    for recorded in history {
+        match &recorded.event {
+            GameEvent::Pile { .. } | GameEvent::Rules { .. } => {}
+            GameEvent::Draw { .. } | GameEvent::Shuffle { .. } | GameEvent::Reorder { .. } => {
+                match recorded.event.movement() {
+                    Some(ref movement) if movement.is_reversible() => done.push(recorded.seq),
+                    _ => done.clear(),
+                }
+                undone.clear();
+            }
+            GameEvent::Flip { .. } | GameEvent::Face { .. } => {
+                done.push(recorded.seq);
+                undone.clear();
+            }
+            GameEvent::Undo { target, .. } => {
+                done.retain(|s| s != target);
+                undone.push(*target);
+            }
+            GameEvent::Redo { target, .. } => {
+                undone.retain(|s| s != target);
+                done.push(*target);
+            }
+        }
+    }
+
+    (done, undone)
+}
+
+// The move an undo would take back
+pub fn next_undo<C: PlayingCard>(history: &Vec<RecordedEvent<C>>) -> Option<(i32, &GameEvent<C>)> {
+    let (done, _) = move_stacks(history);
+    let seq = *done.last()?;
+    find_event(history, seq).map(|event| (seq, event))
+}
+
+// The move a redo would make again
+pub fn next_redo<C: PlayingCard>(history: &Vec<RecordedEvent<C>>) -> Option<(i32, &GameEvent<C>)> {
+    let (_, undone) = move_stacks(history);
+    let seq = *undone.last()?;
+    find_event(history, seq).map(|event| (seq, event))
+}
+
+pub fn find_event<C: PlayingCard>(
+    history: &Vec<RecordedEvent<C>>,
+    seq: i32,
+) -> Option<&GameEvent<C>> {
+    history
+        .iter()
+        .find(|recorded| recorded.seq == seq)
+        .map(|recorded| &recorded.event)
+}
+
+// Every event of a game, oldest first
+pub fn load_events<C: PlayingCard>(
+    conn: &SqliteConnection,
+    id: &str,
+) -> QueryResult<Vec<RecordedEvent<C>>> {
+    use super::schema::game_events::dsl::*;
+
+    game_events
+        .filter(game_id.eq(id))
+        .order(seq.asc())
+        .get_results::<models::Event>(conn)?
+        .into_iter()
+        .map(GameEvent::from_model)
+        .collect()
+}
+
+// Appends events after the last one stored for the game
+pub fn save_events<C: PlayingCard>(
+    conn: &SqliteConnection,
+    id: &str,
+    events: &Vec<GameEvent<C>>,
+) -> QueryResult<usize> {
+    use super::schema::game_events::dsl::*;
+    use diesel::dsl::*;
+
+    let last: Option<i32> = game_events
+        .select(max(seq))
+        .filter(game_id.eq(id))
+        .get_result(conn)?;
+    let first = last.unwrap_or(0) + 1;
+
+    let rows: Vec<models::NewEvent> = events
+        .iter()
+        .enumerate()
+        .map(|(i, e)| e.to_model(id, first + i as i32))
+        .collect();
+    insert_into(game_events).values(&rows).execute(conn)
+}
@@ -0,0 +1,121 @@
+use super::cards::*;
+
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+
+use serde::{Deserialize, Serialize};
+
+use sha2::{Digest, Sha256};
+
+// Commit-reveal for a game's seed. The hash of a secret server seed is published when
+// the game is created, and the seed itself once it's revealed. A fair game also takes a
+// seed from every seat after the commitment is out, so neither side can pick the seed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Fairness {
+    pub commitment: String,
+    pub client_seeds: Vec<String>,
+    // Seats that haven't given their seed yet, the deck is dealt once there are none
+    #[serde(default)]
+    pub awaiting: Vec<usize>,
+    // Kept from players until revealed
+    pub server_seed: String,
+    pub revealed: bool,
+}
+
+impl Fairness {
+    pub fn new(awaiting: Vec<usize>) -> Fairness {
+        let server_seed = to_hex(&thread_rng().gen::<[u8; 32]>());
+        Fairness {
+            commitment: sha256_hex(server_seed.as_bytes()),
+            client_seeds: Vec::new(),
+            awaiting,
+            server_seed,
+            revealed: false,
+        }
+    }
+
+    // The game's seed, the first 8 bytes of the hash of every seed joined with ":"
+    pub fn seed(&self) -> u64 {
+        let mut seeds = vec![self.server_seed.clone()];
+        seeds.extend(self.client_seeds.iter().cloned());
+
+        let hash = Sha256::digest(seeds.join(":").as_bytes());
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash[..8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    pub fn commitment_matches(&self) -> bool {
+        sha256_hex(self.server_seed.as_bytes()) == self.commitment
+    }
+}
+
+// A revealed game's starting deck, rebuilt from its seeds
+#[derive(Serialize, Debug)]
+#[serde(bound = "")]
+pub struct Verification<C: PlayingCard = Card> {
+    pub commitment_matches: bool,
+    pub deck_matches: bool,
+    pub seed: u64,
+    pub deck: CardCollection<C>,
+}
+
+// The generator for the `n`th random selection of a game with this seed
+pub fn seeded_rng(seed: u64, n: u64) -> StdRng {
+    let mut bytes = [0u8; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes[8..16].copy_from_slice(&n.to_le_bytes());
+    StdRng::from_seed(bytes)
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fairness(client_seeds: &[&str]) -> Fairness {
+        let mut fairness = Fairness::new(Vec::new());
+        fairness.client_seeds = client_seeds.iter().map(|s| s.to_string()).collect();
+        fairness
+    }
+
+    #[test]
+    fn seed_comes_from_every_seed() {
+        let fairness = fairness(&["a", "b"]);
+        assert_eq!(fairness.seed(), fairness.clone().seed());
+
+        let mut other = fairness.clone();
+        other.client_seeds[1] = String::from("c");
+        assert_ne!(fairness.seed(), other.seed());
+
+        let mut other = fairness.clone();
+        other.server_seed = Fairness::new(Vec::new()).server_seed;
+        assert_ne!(fairness.seed(), other.seed());
+    }
+
+    #[test]
+    fn commitment_matches_only_the_server_seed() {
+        let mut fairness = fairness(&["a"]);
+        assert!(fairness.commitment_matches());
+
+        fairness.server_seed = Fairness::new(Vec::new()).server_seed;
+        assert!(!fairness.commitment_matches());
+    }
+
+    #[test]
+    fn seeded_rng_differs_by_draw() {
+        let mut first = seeded_rng(1, 0);
+        let mut again = seeded_rng(1, 0);
+        let mut second = seeded_rng(1, 1);
+        let a: u64 = first.gen();
+        assert_eq!(a, again.gen::<u64>());
+        assert_ne!(a, second.gen::<u64>());
+    }
+}
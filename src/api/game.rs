@@ -1,120 +1,1552 @@
 use super::cards::*;
 use super::api::*;
 use super::models;
+use super::pile::Pile;
 
 use diesel::prelude::*;
 
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng};
+
 use serde::{Deserialize, Serialize};
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Game {
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "")]
+pub struct Game<C: PlayingCard = Card> {
     id: String,
-    piles: HashMap<String, RefCell<CardCollection>>,
+    #[serde(default)]
+    rules: RuleSet<C>,
+    // Every random selection comes from this seed and how many came before it
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    random_draws: u64,
+    // Set when the seed was committed to rather than chosen by the client
+    #[serde(default)]
+    fairness: Option<Fairness>,
+    #[serde(default)]
+    players: Vec<Player>,
+    #[serde(default)]
+    turn: Turn,
+    // Piles cards land face down in
+    #[serde(default)]
+    face_down_piles: HashSet<String>,
+    // Every card lying face down, wherever it is. Never shown to players
+    #[serde(default)]
+    face_down: HashSet<C>,
+    // Stored in their own tables rather than the game's json
+    #[serde(default)]
+    piles: HashMap<String, RefCell<Pile<C>>>,
+    // Kept in its own column, bumped on every save
+    #[serde(skip)]
+    version: i32,
+    // Piles that need writing on the next save
+    #[serde(skip)]
+    changed: HashSet<String>,
+    // Events not yet saved
+    #[serde(skip)]
+    pending: Vec<GameEvent<C>>,
 }
 
-impl Game {
-    pub fn new() -> Game {
-        let mut piles = HashMap::new();
-        piles.insert(String::from("deck"), RefCell::new(CardCollection::from(CardSelection::All(true))));
+// A single step of a batch, moving the selected cards from one pile to another
+#[derive(Deserialize, Clone, Debug)]
+#[serde(bound = "")]
+pub struct Move<C: PlayingCard = Card> {
+    pub from: String,
+    pub to: String,
+    pub selection: CardSelection<C>,
+    #[serde(default)]
+    pub placement: Placement,
+}
 
-        Game {
-            id: Game::new_id(),
-            piles,
+impl<C: PlayingCard> Game<C> {
+    pub fn new(rules: RuleSet<C>) -> Result<Game<C>, CardAPIError> {
+        Game::committed(rules, false)
+    }
+
+    // Seeds the game from a committed server seed. A fair game waits for a seed from
+    // every seat before dealing, see give_seed
+    pub fn committed(rules: RuleSet<C>, fair: bool) -> Result<Game<C>, CardAPIError> {
+        let awaiting = if fair {
+            (0..rules.player_count).collect()
+        } else {
+            Vec::new()
+        };
+        let fairness = Fairness::new(awaiting);
+        let mut game = Game::laid_out(rules, fairness.seed())?;
+        if fair {
+            // Checked now rather than once every seed is in
+            game.build_deck(&mut thread_rng())?;
+        } else {
+            game.deal()?;
+        }
+        game.fairness = Some(fairness);
+        Ok(game)
+    }
+
+    // Games with the same seed and rules that make the same moves end up the same
+    pub fn with_seed(rules: RuleSet<C>, seed: u64) -> Result<Game<C>, CardAPIError> {
+        let mut game = Game::laid_out(rules, seed)?;
+        game.deal()?;
+        Ok(game)
+    }
+
+    // Everything but the deck
+    fn laid_out(rules: RuleSet<C>, seed: u64) -> Result<Game<C>, CardAPIError> {
+        if rules.player_count == 0 {
+            return Err(CardAPIError::InvalidRules(String::from(
+                "player_count must be at least 1",
+            )));
+        }
+        if rules.decks == 0 {
+            return Err(CardAPIError::InvalidRules(String::from(
+                "decks must be at least 1",
+            )));
+        }
+        if rules.cards != C::TYPE {
+            return Err(CardAPIError::InvalidRules(format!(
+                "rules for {:?} cards can't make a game of {:?} cards",
+                rules.cards,
+                C::TYPE
+            )));
+        }
+        if C::faces(&rules.definition).is_empty() {
+            return Err(CardAPIError::InvalidRules(String::from(
+                "deck definition has no cards",
+            )));
+        }
+        if rules.jokers > C::jokers().len() {
+            return Err(CardAPIError::InvalidRules(format!(
+                "a deck can have at most {} jokers",
+                C::jokers().len()
+            )));
+        }
+
+        let deck = String::from("deck");
+        let mut names = rules.piles.clone();
+        names.extend(rules.face_down.iter().cloned());
+        let mut game = Game {
+            // Ids name a game rather than being part of its state, two games
+            // from the same seed still need different ones
+            id: new_id(&mut thread_rng()),
+            face_down_piles: rules.face_down.iter().cloned().collect(),
+            rules,
+            seed,
+            random_draws: 0,
+            fairness: None,
+            players: Vec::new(),
+            turn: Turn::default(),
+            face_down: HashSet::new(),
+            piles: HashMap::new(),
+            version: 0,
+            changed: HashSet::new(),
+            pending: Vec::new(),
+        };
+        if let Some(name) = game.rules.piles.iter().find(|n| game.is_private(n)) {
+            return Err(CardAPIError::InvalidRules(format!(
+                "{} is a seat's private pile, made when they join",
+                name
+            )));
+        }
+        // Private piles in face_down lie face down once they're made
+        for name in names {
+            if name != deck && !game.is_private(&name) && !game.has_pile(&name) {
+                game.new_pile(name);
+            }
+        }
+        Ok(game)
+    }
+
+    // The deck is the game's first random selection
+    fn deal(&mut self) -> Result<(), CardAPIError> {
+        let mut rng = self.next_rng();
+        let deck = self.build_deck(&mut rng)?;
+        self.add_pile(String::from("deck"), deck);
+        Ok(())
+    }
+
+    fn build_deck<R: Rng>(&self, rng: &mut R) -> Result<CardCollection<C>, CardAPIError> {
+        CardCollection::from_cards(&C::deck_for(&self.rules), &self.rules.deck, rng).map_err(|e| {
+            match e {
+                CardAPIError::CardNotInCollection => CardAPIError::InvalidRules(String::from(
+                    "deck can only hold cards from its definition, each of them once",
+                )),
+                e => e,
+            }
+        })
+    }
+
+    // A player's part of a fair game's seed. Once every seat has given one the game's
+    // seed is settled and the deck is dealt
+    pub fn give_seed(&mut self, player: &str, seed: String) -> Result<(), CardAPIError> {
+        let seat = self
+            .player(player)
+            .ok_or(CardAPIError::NotFound(String::from("Player")))?
+            .seat;
+        let fairness = match self.fairness.as_mut() {
+            Some(fairness) if !fairness.awaiting.is_empty() => fairness,
+            _ => {
+                return Err(CardAPIError::SeedNotAccepted(String::from(
+                    "the deck has already been dealt",
+                )))
+            }
+        };
+        if !fairness.awaiting.contains(&seat) {
+            return Err(CardAPIError::SeedNotAccepted(format!(
+                "seat {} has already given one",
+                seat
+            )));
+        }
+
+        fairness.awaiting.retain(|s| *s != seat);
+        fairness.client_seeds.push(seed);
+        if fairness.awaiting.is_empty() {
+            self.seed = fairness.seed();
+            self.random_draws = 0;
+            self.deal()?;
+        }
+        Ok(())
+    }
+
+    // Rebuilds a game by replaying its events from the start, stopping after the
+    // event `at` if given
+    pub fn replay(
+        conn: &SqliteConnection,
+        id: String,
+        at: Option<i32>,
+    ) -> Result<Game<C>, CardAPIError> {
+        Game::replay_with(conn, id, at, |_, _| {})
+    }
+
+    // Every event of the game as the viewer could have seen it, along with the piles just
+    // after it as in pile_view. Cards out of the viewer's sight both before and after an
+    // event show as HIDDEN_CARD
+    pub fn history(
+        conn: &SqliteConnection,
+        id: String,
+        viewer: Option<&str>,
+    ) -> Result<Vec<serde_json::Value>, CardAPIError> {
+        let mut steps = Vec::new();
+        let mut seen = HashSet::new();
+        Game::<C>::replay_with(conn, id, None, |recorded, game| {
+            let visible = game.visible_cards(viewer);
+            let mut step = serde_json::to_value(recorded).unwrap();
+            for field in &["cards", "turned", "selection"] {
+                if let Some(value) = step.get_mut(*field) {
+                    Game::<C>::redact(value, &|code| seen.contains(code) || visible.contains(code));
+                }
+            }
+            Game::<C>::redact_positions(&mut step);
+            let piles: serde_json::Map<String, serde_json::Value> = game
+                .piles
+                .keys()
+                .map(|name| (name.clone(), game.pile_view(name, viewer).unwrap()))
+                .collect();
+            step["piles"] = piles.into();
+            steps.push(step);
+            seen = visible;
+        })?;
+        Ok(steps)
+    }
+
+    // The codes of every card the viewer can see where it lies
+    fn visible_cards(&self, viewer: Option<&str>) -> HashSet<String> {
+        self.piles
+            .iter()
+            .filter(|(name, _)| self.owner(name).is_none() || self.owns(name, viewer))
+            .flat_map(|(name, pile)| {
+                let owned = self.owns(name, viewer);
+                pile.borrow()
+                    .iter()
+                    .filter(|card| owned || !self.face_down.contains(card))
+                    .map(|card| card.to_str())
+                    .collect::<Vec<String>>()
+            })
+            .collect()
+    }
+
+    // Swaps every card code in the value that isn't shown for HIDDEN_CARD
+    fn redact(value: &mut serde_json::Value, shown: &dyn Fn(&str) -> bool) {
+        match value {
+            serde_json::Value::String(code) => {
+                if C::from_str(code.clone()).is_some() && !shown(code) {
+                    *value = HIDDEN_CARD.into();
+                }
+            }
+            serde_json::Value::Array(values) => {
+                for value in values {
+                    Game::<C>::redact(value, shown);
+                }
+            }
+            serde_json::Value::Object(values) => {
+                for value in values.values_mut() {
+                    Game::<C>::redact(value, shown);
+                }
+            }
+            _ => {}
         }
     }
 
+    // Anyone following a pile could tell a hidden card by where it was or went, so those
+    // positions are null
+    fn redact_positions(step: &mut serde_json::Value) {
+        let hidden: Vec<bool> = match step.get("cards").and_then(|cards| cards.as_array()) {
+            Some(cards) => cards.iter().map(|card| card == HIDDEN_CARD).collect(),
+            None => return,
+        };
+        for field in &["from_positions", "to_positions"] {
+            if let Some(positions) = step.get_mut(*field).and_then(|p| p.as_array_mut()) {
+                for (position, hidden) in positions.iter_mut().zip(&hidden) {
+                    if *hidden {
+                        *position = serde_json::Value::Null;
+                    }
+                }
+            }
+        }
+    }
+
+    fn replay_with<F>(
+        conn: &SqliteConnection,
+        id: String,
+        at: Option<i32>,
+        mut step: F,
+    ) -> Result<Game<C>, CardAPIError>
+    where
+        F: FnMut(&RecordedEvent<C>, &Game<C>),
+    {
+        use super::schema::games::dsl::games;
+
+        let mut game = Self::from_model(games.find(id).get_result::<models::Game>(conn)?);
+        // Which way cards lie comes from the events too
+        game.face_down_piles = game.rules.face_down.iter().cloned().collect();
+        game.face_down.clear();
+        let history = load_events::<C>(conn, &game.id)?;
+        for recorded in history.iter().filter(|r| at.map_or(true, |at| r.seq <= at)) {
+            game.replay_event(&recorded.event, &history)?;
+            step(recorded, &game);
+        }
+        Ok(game)
+    }
+
+    fn replay_event(
+        &mut self,
+        event: &GameEvent<C>,
+        history: &Vec<RecordedEvent<C>>,
+    ) -> Result<(), CardAPIError> {
+        match event {
+            GameEvent::Pile { name, cards } => self.insert_pile(name.clone(), cards.clone()),
+            GameEvent::Rules { pile, rules } => self.pile(pile)?.borrow_mut().rules = rules.clone(),
+            // The recorded cards and positions, rather than the selection, so random draws
+            // come out the same. Draws recorded without positions went on top
+            GameEvent::Draw {
+                from, to, cards, ..
+            } => match event.movement() {
+                Some(ref m) if m.is_reversible() => {
+                    self.place(m.from, m.to, m.cards, &m.to_positions)?;
+                    self.land(from, to, cards);
+                }
+                _ => {
+                    let selection = CardSelection::Cards(cards.clone());
+                    self.draw_cards(from, to, &selection, &Placement::Top, &mut thread_rng())?;
+                    self.land(from, to, cards);
+                }
+            },
+            GameEvent::Shuffle { .. }
+            | GameEvent::Reorder { .. }
+            | GameEvent::Flip { .. }
+            | GameEvent::Face { .. } => self.make_again(event)?,
+            GameEvent::Undo { target, .. } => {
+                if let Some(target) = find_event(history, *target) {
+                    self.take_back(target)?;
+                }
+            }
+            GameEvent::Redo { target, .. } => {
+                if let Some(target) = find_event(history, *target) {
+                    self.make_again(target)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn id(&self) -> &str {
         self.id.as_str()
     }
 
+    pub fn rules(&self) -> &RuleSet<C> {
+        &self.rules
+    }
+
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // A fresh generator for each random selection, seeded from the game's seed and
+    // the number of selections made so far
+    fn next_rng(&mut self) -> StdRng {
+        let rng = seeded_rng(self.seed, self.random_draws);
+        self.random_draws += 1;
+        rng
+    }
+
+    fn is_revealed(&self) -> bool {
+        self.fairness.as_ref().map_or(false, |f| f.revealed)
+    }
+
+    // Nothing can change before a fair game is dealt, or once its seed is revealed
+    fn check_playing(&self) -> Result<(), CardAPIError> {
+        match &self.fairness {
+            Some(fairness) if fairness.revealed => Err(CardAPIError::GameFinished),
+            Some(fairness) if !fairness.awaiting.is_empty() => {
+                Err(CardAPIError::AwaitingSeeds(fairness.awaiting.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Publishes the server seed. Every draw after this could be predicted, so the game ends
+    pub fn reveal(&mut self) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+        let fairness = self
+            .fairness
+            .as_mut()
+            .ok_or(CardAPIError::NotFound(String::from("Commitment")))?;
+        fairness.revealed = true;
+        Ok(())
+    }
+
+    // Rebuilds the starting deck from the revealed seeds and checks it against the one
+    // the game really started with
+    pub fn verify(&self, history: &Vec<RecordedEvent<C>>) -> Result<Verification<C>, CardAPIError> {
+        let fairness = self
+            .fairness
+            .as_ref()
+            .ok_or(CardAPIError::NotFound(String::from("Commitment")))?;
+        if !fairness.revealed {
+            return Err(CardAPIError::NotRevealed);
+        }
+
+        let seed = fairness.seed();
+        let deck = self.build_deck(&mut seeded_rng(seed, 0))?;
+        let started = history.iter().find_map(|recorded| match &recorded.event {
+            GameEvent::Pile { name, cards } if name == "deck" => Some(cards),
+            _ => None,
+        });
+
+        Ok(Verification {
+            commitment_matches: fairness.commitment_matches(),
+            deck_matches: started.map_or(false, |cards| cards.iter().eq(deck.iter())),
+            seed,
+            deck,
+        })
+    }
+
+    // Fails with the game's current state unless the request expects this version
+    pub fn expect_version(&self, if_match: &IfMatch) -> Result<(), CardAPIError> {
+        if if_match.matches(self.version) {
+            Ok(())
+        } else {
+            Err(CardAPIError::PreconditionFailed(
+                self.version,
+                self.clone().into(),
+            ))
+        }
+    }
+
+    // Saves the game unless someone else has saved it since it was loaded,
+    // in which case the error holds the game as it is now
+    pub fn try_save(&mut self, conn: &SqliteConnection) -> Result<(), CardAPIError> {
+        if self.save(conn)? == 0 {
+            let current: Game<C> = Game::load(conn, self.id.clone())?;
+            return Err(CardAPIError::VersionConflict(
+                current.version,
+                current.into(),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn draw(
         &mut self,
         from: &String,
         to: &String,
-        selection: &CardSelection,
+        selection: &CardSelection<C>,
+        placement: &Placement,
+        viewer: Option<&str>,
     ) -> Result<(), CardAPIError> {
-        let mut p1 = self
+        self.check_playing()?;
+        self.check_blind(from, selection, viewer)?;
+
+        let mut rng = self.next_rng();
+        self.pile(from)?.borrow().check_draw(selection)?;
+        if from != to {
+            // The same rng picks the same cards the draw will
+            let cards = self
+                .pile(from)?
+                .borrow()
+                .selected(selection, &mut rng.clone())?;
+            self.pile(to)?
+                .borrow()
+                .check_accept(&cards, placement, &self.rules.valuation)?;
+        }
+        let mut event = self.draw_cards(from, to, selection, placement, &mut rng)?;
+        if let GameEvent::Draw { cards, turned, .. } = &mut event {
+            *turned = self.land(from, to, cards);
+        }
+        self.changed.insert(from.clone());
+        self.changed.insert(to.clone());
+        self.pending.push(event);
+        Ok(())
+    }
+
+    // Cards moved to another pile end up lying the way that pile's cards do. Returns the
+    // ones that had to be turned over
+    fn land(&mut self, from: &String, to: &String, cards: &CardCollection<C>) -> CardCollection<C> {
+        if from == to {
+            return CardCollection::new();
+        }
+
+        let down = self.face_down_piles.contains(to);
+        let turned: Vec<C> = cards
+            .iter()
+            .filter(|card| self.face_down.contains(card) != down)
+            .cloned()
+            .collect();
+        let turned = CardCollection::from(turned);
+        self.turn_over(&turned);
+        turned
+    }
+
+    fn turn_over(&mut self, cards: &CardCollection<C>) {
+        for card in cards.iter() {
+            if !self.face_down.remove(card) {
+                self.face_down.insert(card.clone());
+            }
+        }
+    }
+
+    fn set_face(&mut self, name: &String, orientation: Orientation) {
+        match orientation {
+            Orientation::Up => self.face_down_piles.remove(name),
+            Orientation::Down => self.face_down_piles.insert(name.clone()),
+        };
+    }
+
+    // Turns the selected cards of a pile over
+    pub fn flip(
+        &mut self,
+        name: &String,
+        selection: &CardSelection<C>,
+        viewer: Option<&str>,
+    ) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+        self.check_blind(name, selection, viewer)?;
+
+        let mut rng = self.next_rng();
+        let cards = self.pile(name)?.borrow().selected(selection, &mut rng)?;
+        self.turn_over(&cards);
+        self.pending.push(GameEvent::Flip {
+            pile: name.clone(),
+            selection: selection.clone(),
+            cards,
+        });
+        Ok(())
+    }
+
+    // Sets which way up the pile's cards lie, turning over any already there to match
+    pub fn turn(&mut self, name: &String, orientation: Orientation) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+
+        let down = orientation == Orientation::Down;
+        let cards: Vec<C> = self
+            .pile(name)?
+            .borrow()
+            .iter()
+            .filter(|card| self.face_down.contains(card) != down)
+            .cloned()
+            .collect();
+        let cards = CardCollection::from(cards);
+        let was = if self.face_down_piles.contains(name) {
+            Orientation::Down
+        } else {
+            Orientation::Up
+        };
+
+        self.set_face(name, orientation);
+        self.turn_over(&cards);
+        self.pending.push(GameEvent::Face {
+            pile: name.clone(),
+            face: orientation,
+            was,
+            cards,
+        });
+        Ok(())
+    }
+
+    pub fn shuffle(&mut self, name: &String, method: &ShuffleMethod) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+
+        let mut rng = self.next_rng();
+        let event = {
+            let mut pile = self.pile(name)?.borrow_mut();
+            let before = pile.cards().clone();
+            pile.shuffle(method, &mut rng)?;
+            GameEvent::Shuffle {
+                pile: name.clone(),
+                method: method.clone(),
+                cards: pile.cards().clone(),
+                from_positions: before.positions(&pile),
+            }
+        };
+        self.changed.insert(name.clone());
+        self.pending.push(event);
+        Ok(())
+    }
+
+    pub fn reorder(&mut self, name: &String, order: &PileOrder) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+
+        let event = {
+            let mut pile = self.pile(name)?.borrow_mut();
+            let before = pile.cards().clone();
+            match order {
+                PileOrder::Reverse => pile.reverse(),
+                PileOrder::Sort { by } => pile.sort(by, &self.rules.valuation),
+            }
+            GameEvent::Reorder {
+                pile: name.clone(),
+                order: order.clone(),
+                cards: pile.cards().clone(),
+                from_positions: before.positions(&pile),
+            }
+        };
+        self.changed.insert(name.clone());
+        self.pending.push(event);
+        Ok(())
+    }
+
+    // Puts every card in the named piles back on top of the deck, a draw for each pile.
+    // Pile rules aren't checked, this is clearing up rather than a move in the game
+    pub fn return_to_deck(&mut self, names: &Vec<String>) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+
+        let deck = String::from("deck");
+        for name in names.iter().filter(|name| **name != deck) {
+            if self.pile(name)?.borrow().remaining() == 0 {
+                continue;
+            }
+
+            let all = CardSelection::All(false);
+            let mut event =
+                self.draw_cards(name, &deck, &all, &Placement::Top, &mut thread_rng())?;
+            if let GameEvent::Draw { cards, turned, .. } = &mut event {
+                *turned = self.land(name, &deck, cards);
+            }
+            self.changed.insert(name.clone());
+            self.changed.insert(deck.clone());
+            self.pending.push(event);
+        }
+        Ok(())
+    }
+
+    // Every card goes back into the deck, piles in name order so the deck comes out the same
+    pub fn reset(&mut self) -> Result<(), CardAPIError> {
+        let mut names: Vec<String> = self.piles.keys().cloned().collect();
+        names.sort();
+        self.return_to_deck(&names)
+    }
+
+    // Moves the selected cards, recording where each card was and where it ended up
+    fn draw_cards<R: Rng + ?Sized>(
+        &self,
+        from: &String,
+        to: &String,
+        selection: &CardSelection<C>,
+        placement: &Placement,
+        rng: &mut R,
+    ) -> Result<GameEvent<C>, CardAPIError> {
+        // Shuffling, sorting and reversing are how a pile gets rearranged
+        if from == to {
+            return Err(CardAPIError::MoveNotAllowed(format!(
+                "can't draw {} onto itself",
+                from
+            )));
+        }
+
+        let mut source = self.pile(from)?.borrow_mut();
+        let mut destination = self.pile(to)?.borrow_mut();
+        let before = source.clone();
+        let cards = source.draw(selection, &mut destination, placement, rng)?;
+        let to_positions = destination.positions(&cards);
+
+        Ok(GameEvent::Draw {
+            from: from.clone(),
+            to: to.clone(),
+            selection: selection.clone(),
+            from_positions: before.positions(&cards),
+            to_positions,
+            cards,
+            turned: CardCollection::new(),
+        })
+    }
+
+    // Moves exactly these cards, putting them at the given positions in `to`
+    fn place(
+        &self,
+        from: &String,
+        to: &String,
+        cards: &CardCollection<C>,
+        positions: &Vec<usize>,
+    ) -> Result<(), CardAPIError> {
+        let mut source = self.pile(from)?.borrow_mut();
+        source.remove(cards)?;
+        if from == to {
+            source.insert_at(cards, positions)
+        } else {
+            self.pile(to)?.borrow_mut().insert_at(cards, positions)
+        }
+    }
+
+    // Puts the cards of the last move back where they came from, lying as they did
+    pub fn undo(&mut self, history: &Vec<RecordedEvent<C>>) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+
+        let (seq, event) = next_undo(history).ok_or(CardAPIError::NothingToUndo)?;
+
+        self.take_back(event)?;
+        if let Some((from, to, cards)) = event.touched() {
+            self.changed.insert(from.clone());
+            self.changed.insert(to.clone());
+            self.pending.push(GameEvent::Undo {
+                target: seq,
+                from: to.clone(),
+                to: from.clone(),
+                cards: cards.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    // Makes the last undone move again, with the cards ending up exactly where they did
+    pub fn redo(&mut self, history: &Vec<RecordedEvent<C>>) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+
+        let (seq, event) = next_redo(history).ok_or(CardAPIError::NothingToRedo)?;
+
+        self.make_again(event)?;
+        if let Some((from, to, cards)) = event.touched() {
+            self.changed.insert(from.clone());
+            self.changed.insert(to.clone());
+            self.pending.push(GameEvent::Redo {
+                target: seq,
+                from: from.clone(),
+                to: to.clone(),
+                cards: cards.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    // Reverses a move, as it was recorded
+    fn take_back(&mut self, event: &GameEvent<C>) -> Result<(), CardAPIError> {
+        if let Some(m) = event.movement() {
+            self.place(m.to, m.from, m.cards, m.from_positions)?;
+        }
+        if let GameEvent::Face { pile, was, .. } = event {
+            self.set_face(pile, *was);
+        }
+        if let Some(turned) = event.turned() {
+            self.turn_over(turned);
+        }
+        Ok(())
+    }
+
+    // Repeats a move, as it was recorded
+    fn make_again(&mut self, event: &GameEvent<C>) -> Result<(), CardAPIError> {
+        if let Some(m) = event.movement() {
+            self.place(m.from, m.to, m.cards, &m.to_positions)?;
+        }
+        if let GameEvent::Face { pile, face, .. } = event {
+            self.set_face(pile, *face);
+        }
+        if let Some(turned) = event.turned() {
+            self.turn_over(turned);
+        }
+        Ok(())
+    }
+
+    fn pile(&self, name: &String) -> Result<&RefCell<Pile<C>>, CardAPIError> {
+        self.piles
+            .get(name)
+            .ok_or(CardAPIError::NotFound(format!("Pile: {}", name.clone())))
+    }
+
+    // Applies the moves in order, if any of them fails none of them are kept
+    pub fn apply(
+        &mut self,
+        moves: &Vec<Move<C>>,
+        viewer: Option<&str>,
+    ) -> Result<(), CardAPIError> {
+        let piles = self.piles.clone();
+        let face_down = self.face_down.clone();
+        let pending = self.pending.len();
+        for (index, m) in moves.iter().enumerate() {
+            if let Err(e) = self.draw(&m.from, &m.to, &m.selection, &m.placement, viewer) {
+                self.piles = piles;
+                self.face_down = face_down;
+                self.pending.truncate(pending);
+                return Err(CardAPIError::MoveFailed(index, Box::new(e)));
+            }
+        }
+        Ok(())
+    }
+
+    // The selected cards as the viewer sees them, see pile_view
+    pub fn peek(
+        &self,
+        name: &String,
+        selection: &CardSelection<C>,
+        viewer: Option<&str>,
+    ) -> Result<serde_json::Value, CardAPIError> {
+        self.check_blind(name, selection, viewer)?;
+        let owned = self.owns(name, viewer);
+        let cards = self.pile(name)?.borrow().peek(selection)?;
+        Ok(cards
+            .iter()
+            .map(|card| self.card_view(card, owned))
+            .collect())
+    }
+
+    // Whether the pile holds face down cards the viewer can't see
+    pub fn hides_cards(&self, name: &String, viewer: Option<&str>) -> Result<bool, CardAPIError> {
+        let pile = self.pile(name)?.borrow();
+        Ok(!self.owns(name, viewer) && pile.iter().any(|card| self.face_down.contains(card)))
+    }
+
+    // Whether a named card is there would give away a face down one
+    fn check_blind(
+        &self,
+        name: &String,
+        selection: &CardSelection<C>,
+        viewer: Option<&str>,
+    ) -> Result<(), CardAPIError> {
+        if !selection.is_blind() && self.hides_cards(name, viewer)? {
+            return Err(CardAPIError::Forbidden(format!(
+                "pile {} has cards face down, pick by position",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    // Only for a viewer who can see every card in the pile
+    pub fn score(&self, name: &String, viewer: Option<&str>) -> Result<u32, CardAPIError> {
+        if self.hides_cards(name, viewer)? {
+            return Err(CardAPIError::Forbidden(format!(
+                "pile {} has cards face down",
+                name
+            )));
+        }
+
+        Ok(self.pile(name)?.borrow().score(&self.rules.valuation))
+    }
+
+    // Sits a new player in the first free seat, giving them their own private piles
+    pub fn join(&mut self, name: Option<String>) -> Result<&Player, CardAPIError> {
+        let seat = (0..self.rules.player_count)
+            .find(|seat| self.players.iter().all(|p| p.seat != *seat))
+            .ok_or(CardAPIError::GameFull)?;
+
+        let bases = self.rules.private_piles.clone();
+        let piles: Vec<String> = bases
+            .iter()
+            .map(|pile| format!("{}-{}", pile, seat))
+            .collect();
+        for pile in &piles {
+            if self.has_pile(pile) {
+                return Err(CardAPIError::AlreadyExists(format!("Pile: {}", pile)));
+            }
+        }
+        for pile in &piles {
+            self.new_pile(pile.clone());
+        }
+
+        // Not from the seed, whoever knows it could work out every player's id
+        self.players.push(Player {
+            id: new_id(&mut thread_rng()),
+            name: name.unwrap_or_else(|| format!("Player {}", seat + 1)),
+            seat,
+            piles,
+        });
+        Ok(self.players.last().unwrap())
+    }
+
+    pub fn player(&self, id: &str) -> Option<&Player> {
+        self.players.iter().find(|p| p.id == id)
+    }
+
+    // The player whose turn it is, nobody until the first seat is taken
+    pub fn current_player(&self) -> Option<&Player> {
+        self.players.iter().find(|p| p.seat == self.turn.seat)
+    }
+
+    pub fn end_turn(&mut self, skip: usize, reverse: bool) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+
+        let seats = self.players.iter().map(|p| p.seat).collect();
+        self.turn.end(&seats, skip, reverse);
+        Ok(())
+    }
+
+    pub fn is_anytime(&self, pile: &String) -> bool {
+        self.rules.anytime_piles.contains(pile)
+    }
+
+    // The piles the viewer can see the card in, in name order. Not someone else's private
+    // pile, and not face down unless the pile is the viewer's own
+    pub fn piles_showing(&self, card: &C, viewer: Option<&str>) -> Vec<&String> {
+        let mut names: Vec<&String> = self
             .piles
-            .get(from)
-            .ok_or(CardAPIError::NotFound(format!("Pile {}", from.clone())))?
-            .borrow_mut();
-        let mut p2 = self
+            .iter()
+            .filter(|(name, pile)| {
+                let owned = self.owns(name, viewer);
+                (self.owner(name).is_none() || owned)
+                    && (owned || !self.face_down.contains(card))
+                    && pile.borrow().iter().any(|c| c == card)
+            })
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    // Named for a seat's private pile, e.g "hand-1", whether or not it's been made yet
+    fn is_private(&self, name: &str) -> bool {
+        self.private_base(name).is_some()
+    }
+
+    // "hand" for "hand-1"
+    fn private_base(&self, name: &str) -> Option<&String> {
+        self.rules.private_piles.iter().find(|base| {
+            name.strip_prefix(base.as_str())
+                .and_then(|rest| rest.strip_prefix('-'))
+                .and_then(|seat| seat.parse::<usize>().ok())
+                .map_or(false, |seat| seat < self.rules.player_count)
+        })
+    }
+
+    pub fn owner(&self, pile: &String) -> Option<&Player> {
+        self.players.iter().find(|p| p.owns(pile))
+    }
+
+    // The game as the caller sees it, see pile_view. Nobody sees a committed seed until
+    // the server seed has been revealed, and only the host sees a chosen one
+    pub fn view(self, caller: &Caller) -> JsonValue {
+        let viewer = caller.player();
+        let piles: serde_json::Map<String, serde_json::Value> = self
             .piles
-            .get(to)
-            .ok_or(CardAPIError::NotFound(format!("Pile: {}", to.clone())))?
-            .borrow_mut();
+            .keys()
+            .map(|name| (name.clone(), self.pile_view(name, viewer).unwrap()))
+            .collect();
+        let pile_rules: serde_json::Map<String, serde_json::Value> = self
+            .piles
+            .iter()
+            .filter(|(_, pile)| pile.borrow().rules != PileRules::default())
+            .map(|(name, pile)| {
+                let rules = serde_json::to_value(&pile.borrow().rules).unwrap();
+                (name.clone(), rules)
+            })
+            .collect();
+        let hidden = match self.fairness {
+            Some(_) => !self.is_revealed(),
+            None => *caller != Caller::Host,
+        };
+
+        let mut json = serde_json::to_value(self).unwrap();
+        let object = json.as_object_mut().unwrap();
+        object.remove("face_down");
+        // A player's id is theirs to know
+        for player in object["players"].as_array_mut().unwrap() {
+            if player["id"].as_str() != viewer {
+                player.as_object_mut().unwrap().remove("id");
+            }
+        }
+        object.insert(String::from("piles"), piles.into());
+        object.insert(String::from("pile_rules"), pile_rules.into());
+        if hidden {
+            // Knowing the seed and how far along it is predicts every draw to come
+            object.remove("seed");
+            object.remove("random_draws");
+            if let Some(fairness) = object.get_mut("fairness").and_then(|f| f.as_object_mut()) {
+                fairness.remove("server_seed");
+            }
+        }
+        JsonValue::from(json)
+    }
+
+    // Another player's private pile is only a count of its cards. Otherwise face down
+    // cards show as HIDDEN_CARD, unless the viewer owns the pile
+    pub fn pile_view(
+        &self,
+        name: &String,
+        viewer: Option<&str>,
+    ) -> Result<serde_json::Value, CardAPIError> {
+        let pile = self.pile(name)?.borrow();
+        let owned = self.owns(name, viewer);
+        if self.owner(name).is_some() && !owned {
+            return Ok(pile.remaining().into());
+        }
+
+        Ok(pile
+            .iter()
+            .map(|card| self.card_view(card, owned))
+            .collect())
+    }
+
+    fn owns(&self, name: &String, viewer: Option<&str>) -> bool {
+        self.owner(name)
+            .map_or(false, |p| Some(p.id.as_str()) == viewer)
+    }
+
+    fn card_view(&self, card: &C, owned: bool) -> serde_json::Value {
+        if !owned && self.face_down.contains(card) {
+            HIDDEN_CARD.into()
+        } else {
+            serde_json::to_value(card).unwrap()
+        }
+    }
+
+    // Replaces the rules a pile's moves are checked against. Cards already there stay
+    pub fn set_pile_rules(
+        &mut self,
+        name: &String,
+        rules: PileRules<C>,
+    ) -> Result<(), CardAPIError> {
+        self.check_playing()?;
 
-        p1.draw(selection, &mut p2)
+        self.pile(name)?.borrow_mut().rules = rules.clone();
+        self.changed.insert(name.clone());
+        self.pending.push(GameEvent::Rules {
+            pile: name.clone(),
+            rules,
+        });
+        Ok(())
+    }
+
+    // A pile asked for by a name nothing has used yet. Private piles wait for their player
+    pub fn open_pile(&mut self, name: String) -> Result<(), CardAPIError> {
+        self.check_playing()?;
+        if self.is_private(&name) {
+            return Err(CardAPIError::Forbidden(format!(
+                "pile {} is made when its seat is taken",
+                name
+            )));
+        }
+        self.new_pile(name);
+        Ok(())
     }
 
     pub fn new_pile(&mut self, name: String) {
-        self.piles.insert(name, RefCell::new(CardCollection::new()));
+        self.add_pile(name, CardCollection::new());
+    }
+
+    fn add_pile(&mut self, name: String, cards: CardCollection<C>) {
+        self.changed.insert(name.clone());
+        self.pending.push(GameEvent::Pile {
+            name: name.clone(),
+            cards: cards.clone(),
+        });
+        self.insert_pile(name, cards);
     }
 
-    pub fn get_pile(&self, name: &String) -> Option<&RefCell<CardCollection>> {
+    // A new pile, its cards lying the way the pile's do
+    fn insert_pile(&mut self, name: String, cards: CardCollection<C>) {
+        // A private pile's rules are given for all of them, "hand" for "hand-1"
+        let rules = self
+            .rules
+            .pile_rules
+            .get(&name)
+            .or_else(|| {
+                self.private_base(&name)
+                    .and_then(|base| self.rules.pile_rules.get(base))
+            })
+            .cloned()
+            .unwrap_or_default();
+        if self.face_down_piles.contains(&name) {
+            self.face_down.extend(cards.iter().cloned());
+        }
+        self.piles
+            .insert(name, RefCell::new(Pile::new(cards, rules)));
+    }
+
+    pub fn get_pile(&self, name: &String) -> Option<&RefCell<Pile<C>>> {
         self.piles.get(name)
     }
 
     pub fn has_pile(&self, name: &String) -> bool {
         self.get_pile(name).is_some()
     }
+}
 
-    fn new_id() -> String {
-        use rand::seq::IteratorRandom;
-        use rand::thread_rng;
+impl<C: PlayingCard> Game<C> {
+    fn load_piles(&mut self, conn: &SqliteConnection) -> QueryResult<()> {
+        use super::schema::pile_cards::dsl::*;
+        use super::schema::piles::dsl::{game_id as pile_game_id, piles};
 
-        (0..26)
-            .chain(32..58)
-            .map(|x| (x + 'A' as u8) as char)
-            .choose_multiple(&mut thread_rng(), 12)
-            .iter()
-            .collect()
+        let names = piles
+            .filter(pile_game_id.eq(&self.id))
+            .get_results::<models::Pile>(conn)?;
+        for p in names {
+            let rules = match p.rules {
+                Some(rules) => serde_json::from_str(&rules)
+                    .map_err(|e| diesel::result::Error::DeserializationError(e.into()))?,
+                None => PileRules::default(),
+            };
+            self.piles.insert(
+                p.name,
+                RefCell::new(Pile::new(CardCollection::new(), rules)),
+            );
+        }
+
+        let cards = pile_cards
+            .filter(game_id.eq(&self.id))
+            .order((pile.asc(), position.asc()))
+            .get_results::<models::PileCard>(conn)?;
+
+        let mut loaded: HashMap<String, Vec<C>> = HashMap::new();
+        for card in cards {
+            let c = C::from_str(card.code.clone()).ok_or_else(|| {
+                diesel::result::Error::DeserializationError(
+                    format!("Invalid card code {}", card.code).into(),
+                )
+            })?;
+            loaded.entry(card.pile).or_insert_with(Vec::new).push(c);
+        }
+        for (name, cards) in loaded {
+            let rules = self
+                .piles
+                .get(&name)
+                .map(|p| p.borrow().rules.clone())
+                .unwrap_or_default();
+            self.piles.insert(
+                name,
+                RefCell::new(Pile::new(CardCollection::from(cards), rules)),
+            );
+        }
+
+        self.changed.clear();
+        Ok(())
+    }
+
+    // Rewrites the cards of each changed pile, top of the pile has the highest position
+    fn save_piles(&self, conn: &SqliteConnection) -> QueryResult<()> {
+        use super::schema::pile_cards::dsl::*;
+        use super::schema::piles::dsl::piles;
+        use diesel::dsl::*;
+
+        for name in &self.changed {
+            let collection = match self.piles.get(name) {
+                Some(collection) => collection.borrow(),
+                None => continue,
+            };
+
+            replace_into(piles)
+                .values(models::Pile {
+                    game_id: self.id.clone(),
+                    name: name.clone(),
+                    rules: if collection.rules == PileRules::default() {
+                        None
+                    } else {
+                        Some(serde_json::to_string(&collection.rules).unwrap())
+                    },
+                })
+                .execute(conn)?;
+
+            delete(
+                pile_cards
+                    .filter(game_id.eq(&self.id))
+                    .filter(pile.eq(name)),
+            )
+            .execute(conn)?;
+
+            let rows: Vec<models::PileCard> = collection
+                .iter()
+                .enumerate()
+                .map(|(i, c)| models::PileCard {
+                    game_id: self.id.clone(),
+                    pile: name.clone(),
+                    position: i as i32,
+                    code: c.to_str(),
+                })
+                .collect();
+            insert_into(pile_cards).values(&rows).execute(conn)?;
+        }
+        Ok(())
+    }
+}
+
+// The kind of card a stored game is played with, so it can be loaded as a Game of them.
+// Games from before there was a choice are French
+pub fn card_type(conn: &SqliteConnection, id: &str) -> Result<CardType, CardAPIError> {
+    use super::schema::games::dsl::{games, json};
+    use diesel::result::Error::DeserializationError;
+
+    let stored: String = games.find(id).select(json).get_result(conn)?;
+    let stored: serde_json::Value =
+        serde_json::from_str(&stored).map_err(|e| DeserializationError(e.into()))?;
+    match stored.get("rules").and_then(|rules| rules.get("cards")) {
+        Some(cards) => Ok(
+            serde_json::from_value(cards.clone()).map_err(|e| DeserializationError(e.into()))?
+        ),
+        None => Ok(CardType::default()),
     }
 }
 
+fn new_id<R: Rng>(rng: &mut R) -> String {
+    use rand::seq::IteratorRandom;
+
+    (0..26)
+        .chain(32..58)
+        .map(|x| (x + 'A' as u8) as char)
+        .choose_multiple(rng, 12)
+        .iter()
+        .collect()
+}
+
 use rocket_contrib::json::JsonValue;
 
-impl std::convert::Into<JsonValue> for Game {
+impl<C: PlayingCard> std::convert::Into<JsonValue> for Game<C> {
+    // As seen by someone who isn't playing
     fn into(self) -> JsonValue {
-        JsonValue::from(serde_json::to_value(self).unwrap())
+        self.view(&Caller::Spectator)
     }
 }
 
-impl models::HasModel for Game {
+impl<C: PlayingCard> models::HasModel for Game<C> {
     type Model = models::Game;
 
     fn from_model(m: Self::Model) -> Self {
-        serde_json::from_str::<Game>(&m.json).unwrap()
+        let mut game = serde_json::from_str::<Game<C>>(&m.json).unwrap();
+        game.version = m.version;
+        game
     }
     fn to_model(&self) -> Self::Model {
+        let mut json = serde_json::to_value(self).unwrap();
+        json.as_object_mut().unwrap().remove("piles");
+
         models::Game {
             id: self.id.clone(),
-            json: serde_json::to_string(self).unwrap(),
+            json: json.to_string(),
+            version: self.version + 1,
         }
     }
 
-    fn save(&self, conn: &SqliteConnection) -> QueryResult<usize> {
+    // Only writes over the version this game was loaded at, returning 0 if it's stale.
+    // Piles that haven't changed since loading are left alone
+    fn save(&mut self, conn: &SqliteConnection) -> QueryResult<usize> {
         use super::schema::games::dsl::*;
         use diesel::dsl::*;
 
-        if select(exists(games.find(self.id.clone()))).get_result(conn)? {
-            update(games.find(self.id.clone()))
-                .set(self.to_model())
-                .execute(conn)
-        } else {
-            insert_into(games).values(self.to_model()).execute(conn)?;
-            Ok(1)
-        }
+        conn.transaction(|| {
+            let saved = if select(exists(games.find(self.id.clone()))).get_result(conn)? {
+                update(games.find(self.id.clone()).filter(version.eq(self.version)))
+                    .set(self.to_model())
+                    .execute(conn)?
+            } else {
+                insert_into(games).values(self.to_model()).execute(conn)?
+            };
+
+            if saved > 0 {
+                self.save_piles(conn)?;
+                save_events(conn, &self.id, &self.pending)?;
+                self.changed.clear();
+                self.pending.clear();
+                self.version += 1;
+            }
+            Ok(saved)
+        })
     }
 
     fn load(conn: &SqliteConnection, id: String) -> QueryResult<Self> {
         use super::schema::games::dsl::games;
 
-        Ok(Self::from_model(
-            games.find(id).get_result::<models::Game>(conn)?,
-        ))
+        let mut game = Self::from_model(games.find(id).get_result::<models::Game>(conn)?);
+        game.load_piles(conn)?;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game() -> Game {
+        let rules = RuleSet {
+            piles: vec![String::from("discard")],
+            ..RuleSet::default()
+        };
+        Game::with_seed(rules, 7).unwrap()
+    }
+
+    // The events not yet saved, numbered as if they had been
+    fn history(game: &Game) -> Vec<RecordedEvent> {
+        game.pending
+            .iter()
+            .enumerate()
+            .map(|(i, event)| RecordedEvent {
+                seq: i as i32 + 1,
+                at: String::new(),
+                event: event.clone(),
+            })
+            .collect()
+    }
+
+    fn codes(game: &Game, name: &str) -> Vec<String> {
+        game.pile(&String::from(name))
+            .unwrap()
+            .borrow()
+            .iter()
+            .map(Card::to_str)
+            .collect()
+    }
+
+    fn draw(game: &mut Game, selection: CardSelection, placement: Placement) {
+        let (deck, discard) = (String::from("deck"), String::from("discard"));
+        game.draw(&deck, &discard, &selection, &placement, None)
+            .unwrap();
+    }
+
+    #[test]
+    fn undo_puts_cards_back_where_they_were() {
+        let mut game = game();
+        draw(&mut game, CardSelection::Top(3), Placement::Top);
+        let before = (codes(&game, "deck"), codes(&game, "discard"));
+
+        draw(&mut game, CardSelection::Random(5), Placement::Random);
+        let after = (codes(&game, "deck"), codes(&game, "discard"));
+        assert_ne!(before, after);
+
+        game.undo(&history(&game)).unwrap();
+        assert_eq!((codes(&game, "deck"), codes(&game, "discard")), before);
+
+        game.redo(&history(&game)).unwrap();
+        assert_eq!((codes(&game, "deck"), codes(&game, "discard")), after);
+    }
+
+    #[test]
+    fn undo_steps_back_through_moves_in_order() {
+        let mut game = game();
+        let start = codes(&game, "deck");
+        draw(&mut game, CardSelection::Bottom(2), Placement::Index(0));
+        let drawn = codes(&game, "deck");
+        game.shuffle(&String::from("deck"), &ShuffleMethod::Wash)
+            .unwrap();
+
+        game.undo(&history(&game)).unwrap();
+        assert_eq!(codes(&game, "deck"), drawn);
+        game.undo(&history(&game)).unwrap();
+        assert_eq!(codes(&game, "deck"), start);
+        assert!(codes(&game, "discard").is_empty());
+
+        match game.undo(&history(&game)) {
+            Err(CardAPIError::NothingToUndo) => {}
+            other => panic!("expected NothingToUndo, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_fair_game_deals_once_every_seat_has_a_seed_and_verifies() {
+        let rules = RuleSet {
+            player_count: 2,
+            ..RuleSet::default()
+        };
+        let mut game: Game = Game::committed(rules, true).unwrap();
+        assert!(!game.has_pile(&String::from("deck")));
+
+        let first = game.join(None).unwrap().id.clone();
+        let second = game.join(None).unwrap().id.clone();
+        game.give_seed(&first, String::from("a")).unwrap();
+        match game.give_seed(&first, String::from("b")) {
+            Err(CardAPIError::SeedNotAccepted(_)) => {}
+            other => panic!("expected SeedNotAccepted, got {:?}", other),
+        }
+        assert!(!game.has_pile(&String::from("deck")));
+
+        game.give_seed(&second, String::from("b")).unwrap();
+        assert_eq!(codes(&game, "deck").len(), 52);
+
+        game.reveal().unwrap();
+        let verification = game.verify(&history(&game)).unwrap();
+        assert!(verification.commitment_matches);
+        assert!(verification.deck_matches);
+        assert_eq!(verification.seed, game.seed());
+    }
+
+    #[test]
+    fn only_the_host_sees_a_chosen_seed() {
+        let host = game().view(&Caller::Host);
+        assert_eq!(host["seed"], 7);
+        assert!(host.get("random_draws").is_some());
+
+        let mut game = game();
+        let player = Caller::Player(game.join(None).unwrap().id.clone());
+        for view in vec![game.clone().view(&player), game.view(&Caller::Spectator)] {
+            assert!(view.get("seed").is_none());
+            assert!(view.get("random_draws").is_none());
+        }
+    }
+
+    #[test]
+    fn player_ids_dont_follow_from_the_seed() {
+        let (mut first, mut second) = (game(), game());
+        assert_ne!(first.join(None).unwrap().id, second.join(None).unwrap().id);
+    }
+
+    // Two seats with a hand each, and the deck face down
+    fn table() -> (Game, String, String) {
+        let rules = RuleSet {
+            player_count: 2,
+            piles: vec![String::from("discard")],
+            private_piles: vec![String::from("hand")],
+            face_down: vec![String::from("deck")],
+            ..RuleSet::default()
+        };
+        let mut game: Game = Game::with_seed(rules, 7).unwrap();
+        let first = game.join(None).unwrap().id.clone();
+        let second = game.join(None).unwrap().id.clone();
+        (game, first, second)
+    }
+
+    fn named(code: &str) -> CardSelection {
+        let card = Card::from_str(String::from(code)).unwrap();
+        CardSelection::Cards(CardCollection::from(vec![card]))
+    }
+
+    #[test]
+    fn other_players_see_a_private_pile_as_a_count() {
+        let (mut game, first, second) = table();
+        let (deck, hand) = (String::from("deck"), String::from("hand-0"));
+        game.draw(&deck, &hand, &CardSelection::Top(2), &Placement::Top, None)
+            .unwrap();
+
+        let own = game.pile_view(&hand, Some(&first)).unwrap();
+        assert_eq!(own, serde_json::to_value(codes(&game, "hand-0")).unwrap());
+        assert_eq!(game.pile_view(&hand, Some(&second)).unwrap(), 2);
+        assert_eq!(game.pile_view(&hand, None).unwrap(), 2);
+
+        let view = game.view(&Caller::Player(second));
+        assert_eq!(view["piles"]["hand-0"], 2);
+        assert_eq!(view["piles"]["hand-1"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn face_down_cards_are_only_visible_once_turned_up() {
+        let (mut game, first, _) = table();
+        let deck = String::from("deck");
+        let top = codes(&game, "deck").pop().unwrap();
+        let shown = game.pile_view(&deck, Some(&first)).unwrap();
+        assert!(shown
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|card| card == HIDDEN_CARD));
+        assert!(game.visible_cards(Some(&first)).is_empty());
+
+        let discard = String::from("discard");
+        game.draw(
+            &deck,
+            &discard,
+            &CardSelection::Top(1),
+            &Placement::Top,
+            None,
+        )
+        .unwrap();
+        let visible = game.visible_cards(None);
+        assert_eq!(
+            visible.into_iter().collect::<Vec<String>>(),
+            vec![top.clone()]
+        );
+        assert_eq!(
+            game.pile_view(&discard, None).unwrap(),
+            serde_json::json!([top])
+        );
+    }
+
+    #[test]
+    fn face_down_piles_can_only_be_picked_from_by_position() {
+        let (mut game, _, _) = table();
+        let (deck, discard) = (String::from("deck"), String::from("discard"));
+        let top = codes(&game, "deck").pop().unwrap();
+
+        match game.draw(&deck, &discard, &named(&top), &Placement::Top, None) {
+            Err(CardAPIError::Forbidden(_)) => {}
+            other => panic!("expected Forbidden, got {:?}", other),
+        }
+        match game.flip(&deck, &named(&top), None) {
+            Err(CardAPIError::Forbidden(_)) => {}
+            other => panic!("expected Forbidden, got {:?}", other),
+        }
+        let moves = vec![Move {
+            from: deck.clone(),
+            to: discard.clone(),
+            selection: named(&top),
+            placement: Placement::Top,
+        }];
+        match game.apply(&moves, None) {
+            Err(CardAPIError::MoveFailed(0, _)) => {}
+            other => panic!("expected MoveFailed, got {:?}", other),
+        }
+
+        game.flip(&deck, &CardSelection::Top(1), None).unwrap();
+        game.draw(
+            &deck,
+            &discard,
+            &CardSelection::Top(1),
+            &Placement::Top,
+            None,
+        )
+        .unwrap();
+        assert_eq!(codes(&game, "discard"), vec![top]);
+    }
+
+    #[test]
+    fn redact_hides_the_codes_not_shown() {
+        let mut value = serde_json::json!({
+            "pile": "deck",
+            "cards": ["AS", "KH"],
+            "selection": { "cards": ["KH"] },
+            "from_positions": [3, 4],
+        });
+        Game::<Card>::redact(&mut value, &|code| code == "AS");
+        Game::<Card>::redact_positions(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "pile": "deck",
+                "cards": ["AS", "??"],
+                "selection": { "cards": ["??"] },
+                "from_positions": [3, null],
+            })
+        );
+    }
+
+    #[test]
+    fn a_new_move_clears_what_can_be_redone() {
+        let mut game = game();
+        draw(&mut game, CardSelection::Top(1), Placement::Top);
+        game.undo(&history(&game)).unwrap();
+        draw(&mut game, CardSelection::Top(2), Placement::Bottom);
+
+        match game.redo(&history(&game)) {
+            Err(CardAPIError::NothingToRedo) => {}
+            other => panic!("expected NothingToRedo, got {:?}", other),
+        }
     }
 }
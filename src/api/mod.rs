@@ -1,15 +1,31 @@
+mod auth;
 mod error;
+mod etag;
+mod events;
+mod fairness;
+mod pile;
+mod player;
+mod turn;
 mod cards;
 mod routes;
 mod game;
+mod ruleset;
 mod stringcode;
 mod models;
 
 use super::*;
 
+pub use auth::*;
 pub use error::*;
+pub use etag::*;
+pub use events::*;
+pub use fairness::*;
+pub use pile::{Build, PileRules, RankRule, SuitRule};
+pub use player::*;
+pub use turn::*;
 pub use cards::*;
 pub use routes::*;
 pub use game::*;
+pub use ruleset::*;
 pub use stringcode::*;
 pub use models::*;
@@ -1,4 +1,4 @@
-use super::schema::games;
+use super::schema::{game_events, games, pile_cards, piles, tokens};
 
 use diesel::prelude::*;
 
@@ -8,7 +8,7 @@ pub trait HasModel {
     fn load(conn: &SqliteConnection, id: String) -> QueryResult<Self>
     where
         Self: std::marker::Sized;
-    fn save(&self, conn: &SqliteConnection) -> QueryResult<usize>;
+    fn save(&mut self, conn: &SqliteConnection) -> QueryResult<usize>;
 
     fn from_model(m: Self::Model) -> Self;
     fn to_model(&self) -> Self::Model;
@@ -19,4 +19,66 @@ pub trait HasModel {
 pub struct Game {
     pub id: String,
     pub json: String,
+    pub version: i32,
+}
+
+#[derive(Identifiable, Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "piles"]
+#[primary_key(game_id, name)]
+pub struct Pile {
+    pub game_id: String,
+    pub name: String,
+    pub rules: Option<String>,
+}
+
+#[derive(Identifiable, Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "pile_cards"]
+#[primary_key(game_id, pile, position)]
+pub struct PileCard {
+    pub game_id: String,
+    pub pile: String,
+    pub position: i32,
+    pub code: String,
+}
+
+#[derive(Queryable, PartialEq, Debug)]
+pub struct Event {
+    pub game_id: String,
+    pub seq: i32,
+    pub kind: String,
+    pub from_pile: Option<String>,
+    pub to_pile: Option<String>,
+    pub selection: Option<String>,
+    pub cards: String,
+    pub created_at: String,
+    pub from_positions: Option<String>,
+    pub to_positions: Option<String>,
+    pub target: Option<i32>,
+    pub turned: Option<String>,
+}
+
+// Events are timestamped by the database as they're inserted
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "game_events"]
+pub struct NewEvent {
+    pub game_id: String,
+    pub seq: i32,
+    pub kind: String,
+    pub from_pile: Option<String>,
+    pub to_pile: Option<String>,
+    pub selection: Option<String>,
+    pub cards: String,
+    pub from_positions: Option<String>,
+    pub to_positions: Option<String>,
+    pub target: Option<i32>,
+    pub turned: Option<String>,
+}
+
+#[derive(Identifiable, Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "tokens"]
+#[primary_key(hash)]
+pub struct Token {
+    pub hash: String,
+    pub game_id: String,
+    pub player_id: Option<String>,
 }
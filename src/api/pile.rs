@@ -0,0 +1,353 @@
+use super::cards::*;
+use super::error::CardAPIError;
+use super::stringcode::HasStringCode;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::ops::{Deref, DerefMut};
+
+// A pile's cards along with its rules. Serialized as just the cards, the rules are
+// stored alongside the pile
+#[derive(Clone, Debug)]
+pub struct Pile<C: PlayingCard = Card> {
+    cards: CardCollection<C>,
+    pub rules: PileRules<C>,
+}
+
+// Limits on what a pile holds and how cards come and go, none by default
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields, bound = "")]
+pub struct PileRules<C: PlayingCard = Card> {
+    // Most cards the pile can hold, e.g 1 for a discard slot
+    pub capacity: Option<usize>,
+    // The selections cards can be taken with, e.g ["top"] for a stock. Any if empty
+    pub draw: Vec<SelectionKind>,
+    // How each card put on the pile has to follow the one it lands on
+    pub build: Option<Build<C>>,
+}
+
+// e.g a solitaire tableau is { "rank": "down", "suit": "alternate", "empty": "K" }
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields, bound = "")]
+pub struct Build<C: PlayingCard = Card> {
+    pub rank: RankRule,
+    pub suit: SuitRule,
+    // The only value that can go on the pile while it's empty
+    pub empty: Option<C::Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RankRule {
+    Any,
+    Same,
+    // One rank higher than the card it goes on, under the game's ranking
+    Up,
+    Down,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SuitRule {
+    Any,
+    Same,
+    // The other colour, cards without a colour never alternate
+    Alternate,
+}
+
+impl<C: PlayingCard> Default for PileRules<C> {
+    fn default() -> Self {
+        PileRules {
+            capacity: None,
+            draw: Vec::new(),
+            build: None,
+        }
+    }
+}
+
+impl<C: PlayingCard> Default for Build<C> {
+    fn default() -> Self {
+        Build {
+            rank: RankRule::default(),
+            suit: SuitRule::default(),
+            empty: None,
+        }
+    }
+}
+
+impl Default for RankRule {
+    fn default() -> Self {
+        RankRule::Any
+    }
+}
+
+impl Default for SuitRule {
+    fn default() -> Self {
+        SuitRule::Any
+    }
+}
+
+impl<C: PlayingCard> Pile<C> {
+    pub fn new(cards: CardCollection<C>, rules: PileRules<C>) -> Pile<C> {
+        Pile { cards, rules }
+    }
+
+    pub fn cards(&self) -> &CardCollection<C> {
+        &self.cards
+    }
+
+    pub fn check_draw(&self, selection: &CardSelection<C>) -> Result<(), CardAPIError> {
+        let kind = selection.kind();
+        if self.rules.draw.is_empty() || self.rules.draw.contains(&kind) {
+            Ok(())
+        } else {
+            let kinds: Vec<&str> = self.rules.draw.iter().map(SelectionKind::name).collect();
+            Err(CardAPIError::SelectionNotAllowed(format!(
+                "only {} from this pile",
+                kinds.join(", ")
+            )))
+        }
+    }
+
+    // Whether the cards can be put on this pile, in the order they'd land
+    pub fn check_accept<R: CardRanking<C>>(
+        &self,
+        cards: &CardCollection<C>,
+        placement: &Placement,
+        ranking: &R,
+    ) -> Result<(), CardAPIError> {
+        if let Some(capacity) = self.rules.capacity {
+            if self.cards.remaining() + cards.remaining() > capacity {
+                return Err(CardAPIError::PileFull(capacity));
+            }
+        }
+
+        let build = match &self.rules.build {
+            Some(build) => build,
+            None => return Ok(()),
+        };
+        match placement {
+            Placement::Top => {}
+            _ => {
+                return Err(CardAPIError::MoveNotAllowed(String::from(
+                    "cards can only go on top of this pile",
+                )))
+            }
+        }
+
+        let mut top = self.cards.iter().last();
+        for card in cards.iter() {
+            build.check(top, card, ranking)?;
+            top = Some(card);
+        }
+        Ok(())
+    }
+}
+
+impl<C: PlayingCard> Build<C> {
+    fn check<R: CardRanking<C>>(
+        &self,
+        top: Option<&C>,
+        card: &C,
+        ranking: &R,
+    ) -> Result<(), CardAPIError> {
+        let top = match (top, &self.empty) {
+            (Some(top), _) => top,
+            (None, Some(value)) if card.value() != value => {
+                return Err(CardAPIError::MoveNotAllowed(format!(
+                    "only {} can start this pile",
+                    value.to_str()
+                )))
+            }
+            (None, _) => return Ok(()),
+        };
+
+        let (rank, on) = (ranking.rank(card.value()), ranking.rank(top.value()));
+        let rank_follows = match self.rank {
+            RankRule::Any => true,
+            RankRule::Same => rank == on,
+            RankRule::Up => rank == on + 1,
+            RankRule::Down => rank + 1 == on,
+        };
+        let suit_follows = match self.suit {
+            SuitRule::Any => true,
+            SuitRule::Same => card.suit().is_some() && card.suit() == top.suit(),
+            SuitRule::Alternate => match (card.colour(), top.colour()) {
+                (Some(a), Some(b)) => a != b,
+                _ => false,
+            },
+        };
+
+        if rank_follows && suit_follows {
+            Ok(())
+        } else {
+            Err(CardAPIError::MoveNotAllowed(format!(
+                "{} can't go on {}",
+                card.to_str(),
+                top.to_str()
+            )))
+        }
+    }
+}
+
+impl<C: PlayingCard> From<CardCollection<C>> for Pile<C> {
+    fn from(cards: CardCollection<C>) -> Self {
+        Pile::new(cards, PileRules::default())
+    }
+}
+
+impl<C: PlayingCard> Deref for Pile<C> {
+    type Target = CardCollection<C>;
+
+    fn deref(&self) -> &CardCollection<C> {
+        &self.cards
+    }
+}
+
+impl<C: PlayingCard> DerefMut for Pile<C> {
+    fn deref_mut(&mut self) -> &mut CardCollection<C> {
+        &mut self.cards
+    }
+}
+
+impl<C: PlayingCard> Serialize for Pile<C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.cards.serialize(serializer)
+    }
+}
+
+impl<'de, C: PlayingCard> Deserialize<'de> for Pile<C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CardCollection::deserialize(deserializer).map(Pile::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(codes: &[&str]) -> CardCollection {
+        let cards: Vec<Card> = codes
+            .iter()
+            .map(|code| Card::from_str(code.to_string()).unwrap())
+            .collect();
+        CardCollection::from(cards)
+    }
+
+    fn pile(codes: &[&str], rules: PileRules) -> Pile {
+        Pile::new(cards(codes), rules)
+    }
+
+    fn accepts(pile: &Pile, codes: &[&str]) -> Result<(), CardAPIError> {
+        pile.check_accept(&cards(codes), &Placement::Top, &Valuation::default())
+    }
+
+    fn tableau() -> PileRules {
+        PileRules {
+            build: Some(Build {
+                rank: RankRule::Down,
+                suit: SuitRule::Alternate,
+                empty: Some(CardValue::King),
+            }),
+            ..PileRules::default()
+        }
+    }
+
+    #[test]
+    fn build_only_starts_with_the_empty_value() {
+        let pile = pile(&[], tableau());
+        assert!(accepts(&pile, &["KS"]).is_ok());
+        match accepts(&pile, &["QH"]) {
+            Err(CardAPIError::MoveNotAllowed(_)) => {}
+            other => panic!("expected MoveNotAllowed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn build_follows_rank_and_colour() {
+        let pile = pile(&["KS"], tableau());
+        assert!(accepts(&pile, &["QH", "JC"]).is_ok());
+        assert!(accepts(&pile, &["QC"]).is_err());
+        assert!(accepts(&pile, &["JH"]).is_err());
+        assert!(accepts(&pile, &["QH", "JD"]).is_err());
+    }
+
+    #[test]
+    fn build_same_rank_and_suit() {
+        let build = Build {
+            rank: RankRule::Same,
+            suit: SuitRule::Any,
+            empty: None,
+        };
+        let ranking = Valuation::default();
+        let top = Card::from_str(String::from("7S")).unwrap();
+        let seven = Card::from_str(String::from("7H")).unwrap();
+        let eight = Card::from_str(String::from("8S")).unwrap();
+        assert!(build.check(Some(&top), &seven, &ranking).is_ok());
+        assert!(build.check(Some(&top), &eight, &ranking).is_err());
+
+        let build = Build {
+            rank: RankRule::Up,
+            suit: SuitRule::Same,
+            empty: None,
+        };
+        assert!(build.check(Some(&top), &eight, &ranking).is_ok());
+        assert!(build.check(Some(&seven), &eight, &ranking).is_err());
+        assert!(build.check(None, &seven, &ranking).is_ok());
+    }
+
+    #[test]
+    fn build_only_takes_cards_on_top() {
+        let pile = pile(&["KS"], tableau());
+        let result = pile.check_accept(&cards(&["QH"]), &Placement::Bottom, &Valuation::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn capacity_counts_cards_already_there() {
+        let rules = PileRules {
+            capacity: Some(2),
+            ..PileRules::default()
+        };
+        let pile = pile(&["AS"], rules);
+        assert!(accepts(&pile, &["2S"]).is_ok());
+        match accepts(&pile, &["2S", "3S"]) {
+            Err(CardAPIError::PileFull(2)) => {}
+            other => panic!("expected PileFull, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn check_draw_allows_only_the_listed_selections() {
+        let rules = PileRules {
+            draw: vec![SelectionKind::Top],
+            ..PileRules::default()
+        };
+        let stock = pile(&["AS", "2S"], rules);
+        assert!(stock.check_draw(&CardSelection::Top(1)).is_ok());
+        match stock.check_draw(&CardSelection::Random(1)) {
+            Err(CardAPIError::SelectionNotAllowed(_)) => {}
+            other => panic!("expected SelectionNotAllowed, got {:?}", other),
+        }
+
+        let open = pile(&["AS"], PileRules::default());
+        assert!(open.check_draw(&CardSelection::Random(1)).is_ok());
+    }
+
+    #[test]
+    fn rules_only_take_known_selections_and_values() {
+        let rules: PileRules =
+            serde_json::from_str(r#"{ "draw": ["top"], "build": { "empty": "K" } }"#).unwrap();
+        assert_eq!(rules.draw, vec![SelectionKind::Top]);
+        assert_eq!(rules.build.unwrap().empty, Some(CardValue::King));
+
+        for body in &[
+            r#"{ "draw": ["sideways"] }"#,
+            r#"{ "build": { "empty": "KS" } }"#,
+            r#"{ "build": { "empty": "K", "colour": "red" } }"#,
+            r#"{ "capacity": 1, "limit": 2 }"#,
+        ] {
+            assert!(serde_json::from_str::<PileRules>(body).is_err(), "{}", body);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+// Someone sitting at a game, along with the piles only they get to see
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Player {
+    pub id: String,
+    pub name: String,
+    // From 0 up to the game's player_count
+    pub seat: usize,
+    #[serde(default)]
+    pub piles: Vec<String>,
+}
+
+impl Player {
+    pub fn owns(&self, pile: &String) -> bool {
+        self.piles.contains(pile)
+    }
+}
@@ -1,9 +1,74 @@
-use super::CardSelection;
+use super::{CardAPIError, CardSelection, CardType, Orientation, Placement, PlayingCard, SortBy};
 
+use rocket_contrib::json::{Json, JsonError};
+
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
+// An optional JSON body. Sending none is fine, sending one that doesn't parse isn't
+pub fn optional<T>(body: Result<Json<T>, JsonError>) -> Result<Option<T>, CardAPIError> {
+    match body {
+        Ok(json) => Ok(Some(json.into_inner())),
+        Err(JsonError::Parse(raw, _)) if raw.trim().is_empty() => Ok(None),
+        Err(JsonError::Parse(_, e)) => Err(CardAPIError::InvalidBody(e.to_string())),
+        Err(JsonError::Io(e)) => Err(CardAPIError::InvalidBody(e.to_string())),
+    }
+}
+
+// A JSON body that has to be sent
+pub fn required<T>(body: Result<Json<T>, JsonError>) -> Result<T, CardAPIError> {
+    optional(body)?.ok_or_else(|| CardAPIError::InvalidBody(String::from("no body sent")))
+}
+
+// Bodies holding cards are read before the game's card type is known, and parsed as
+// that type once it is
+pub fn parse<T: DeserializeOwned>(body: serde_json::Value) -> Result<T, CardAPIError> {
+    serde_json::from_value(body).map_err(|e| CardAPIError::InvalidBody(e.to_string()))
+}
+
+// The card type a rule set is for, the rest is parsed once it's known
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct RulesFor {
+    pub(super) cards: CardType,
+}
+
 #[derive(Deserialize)]
-pub struct DrawData {
+#[serde(bound = "")]
+pub struct DrawData<C: PlayingCard> {
     pub(super) source: String,
-    pub(super) selection: CardSelection,
+    pub(super) selection: CardSelection<C>,
+    #[serde(default)]
+    pub(super) placement: Placement,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SortData {
+    pub(super) by: SortBy,
+}
+
+#[derive(Deserialize)]
+pub struct JoinData {
+    pub(super) name: Option<String>,
+    // The player's part of a fair game's seed
+    pub(super) seed: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SeedData {
+    pub(super) seed: String,
+}
+
+#[derive(Deserialize)]
+pub struct FaceData {
+    pub(super) face: Orientation,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct TurnData {
+    // Players passed over, e.g 1 for a skip card
+    pub(super) skip: usize,
+    pub(super) reverse: bool,
 }
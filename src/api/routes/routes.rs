@@ -1,45 +1,616 @@
 use super::*;
 
-use rocket_contrib::json::JsonValue;
+use diesel::Connection;
+use rocket_contrib::json::{Json, JsonError, JsonValue};
 
-#[post("/game/new")]
-pub fn new_game(conn: GamesDbConn) -> Result<JsonValue, CardAPIError> {
-    let game = Game::new();
-    game.save(&conn)?;
-    Ok(game.into())
+// Runs the body with `C` standing for the card type of the game it's about
+macro_rules! with_cards {
+    ($cards:expr, $C:ident => $body:block) => {
+        match $cards {
+            CardType::French => {
+                type $C = Card;
+                $body
+            }
+            CardType::Tarot => {
+                type $C = TarotCard;
+                $body
+            }
+            CardType::Uno => {
+                type $C = UnoCard;
+                $body
+            }
+            CardType::Hanafuda => {
+                type $C = HanafudaCard;
+                $body
+            }
+        }
+    };
+}
+
+#[post("/game/new?<seed>&<fair>", data = "<rules>")]
+pub fn new_game(
+    conn: GamesDbConn,
+    seed: Option<u64>,
+    fair: Option<bool>,
+    rules: Result<Json<serde_json::Value>, JsonError>,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let rules = optional(rules)?.unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+    let cards = parse::<RulesFor>(rules.clone())?.cards;
+    let fair = fair.unwrap_or(false);
+
+    with_cards!(cards, C => {
+        let rules: RuleSet<C> = parse(rules)?;
+        let mut game: Game<C> = match seed {
+            Some(_) if fair => {
+                return Err(CardAPIError::InvalidRules(String::from(
+                    "seed and fair can't be used together",
+                )))
+            }
+            Some(seed) => Game::with_seed(rules, seed)?,
+            None => Game::committed(rules, fair)?,
+        };
+        let token = conn.transaction::<_, CardAPIError, _>(|| {
+            game.try_save(&conn)?;
+            issue_token(&conn, game.id(), None)
+        })?;
+
+        let version = game.version();
+        let mut json: JsonValue = game.view(&Caller::Host);
+        json["token"] = token.into();
+        Ok(Tagged(version, json))
+    })
+}
+
+// The piles the caller can see the card in
+#[get("/game/<id>/find?<card>&<pile>")]
+pub fn find_card(
+    conn: GamesDbConn,
+    id: String,
+    card: String,
+    pile: Option<String>,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let card = C::from_str(card.clone())
+            .ok_or_else(|| CardAPIError::InvalidSelection(format!("no card {}", card)))?;
+        let game: Game<C> = Game::load(&conn, id)?;
+        let piles: Vec<&String> = game
+            .piles_showing(&card, caller.player())
+            .into_iter()
+            .filter(|name| pile.as_ref().map_or(true, |pile| pile == *name))
+            .collect();
+        Ok(Tagged(game.version(), json!({ "piles": piles })))
+    })
+}
+
+#[get("/game/<id>", rank = 2)]
+pub fn get_game(
+    conn: GamesDbConn,
+    id: String,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let game: Game<C> = Game::load(&conn, id)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+// Untagged, a past state isn't a version that can be written over
+#[get("/game/<id>?<at>")]
+pub fn get_game_at(
+    conn: GamesDbConn,
+    id: String,
+    at: i32,
+    bearer: Bearer,
+) -> Result<JsonValue, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let game: Game<C> = Game::replay(&conn, id, Some(at))?;
+        Ok(game.view(&caller))
+    })
+}
+
+#[post("/game/<id>/join", data = "<joindata>")]
+pub fn join_game(
+    conn: GamesDbConn,
+    id: String,
+    joindata: Result<Json<JoinData>, JsonError>,
+    if_match: IfMatch,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let (name, seed) = optional(joindata)?.map_or((None, None), |j| (j.name, j.seed));
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        let player = game.join(name)?.clone();
+        if let Some(seed) = seed {
+            game.give_seed(&player.id, seed)?;
+        }
+        let token = conn.transaction::<_, CardAPIError, _>(|| {
+            game.try_save(&conn)?;
+            issue_token(&conn, game.id(), Some(&player.id))
+        })?;
+        Ok(Tagged(
+            game.version(),
+            json!({ "player": player, "token": token, "game": game.view(&Caller::Player(player.id.clone())) }),
+        ))
+    })
+}
+
+#[post("/game/<id>/seed", data = "<seeddata>")]
+pub fn give_seed(
+    conn: GamesDbConn,
+    id: String,
+    seeddata: Json<SeedData>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    caller.require_token()?;
+    let player = caller.player().ok_or(CardAPIError::Forbidden(String::from(
+        "only players give a seed",
+    )))?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        game.give_seed(player, seeddata.into_inner().seed)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[get("/game/<id>/events")]
+pub fn get_events(
+    conn: GamesDbConn,
+    id: String,
+    bearer: Bearer,
+) -> Result<JsonValue, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    caller.require_token()?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut events = Game::<C>::history(&conn, id, caller.player())?;
+        for event in events.iter_mut() {
+            event.as_object_mut().unwrap().remove("piles");
+        }
+        Ok(json!({ "events": events }))
+    })
+}
+
+#[get("/game/<id>/replay")]
+pub fn replay_game(
+    conn: GamesDbConn,
+    id: String,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let game: Game<C> = Game::replay(&conn, id, None)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
 }
 
-#[get("/game/<id>")]
-pub fn get_game(conn: GamesDbConn, id: String) -> Result<JsonValue, CardAPIError> {
-    let game = Game::load(&conn, id)?;
-    Ok(game.into())
+#[get("/game/<id>/history")]
+pub fn get_history(
+    conn: GamesDbConn,
+    id: String,
+    bearer: Bearer,
+) -> Result<JsonValue, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    caller.require_token()?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let steps = Game::<C>::history(&conn, id.clone(), caller.player())?;
+        Ok(json!({ "id": id, "steps": steps }))
+    })
 }
 
 #[get("/game/<id>/<name>", rank = 2)]
-pub fn get_pile(conn: GamesDbConn, id: String, name: String) -> Result<JsonValue, CardAPIError> {
-    let mut game = Game::load(&conn, id)?;
-    let pile = if game.has_pile(&name) {
-        game.get_pile(&name)
-    } else {
-        game.new_pile(name.clone());
-        game.save(&conn)?;
-        game.get_pile(&name)
-    };
+pub fn get_pile(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        if !game.has_pile(&name) {
+            caller.require_token()?;
+            game.open_pile(name.clone())?;
+            game.try_save(&conn)?;
+        }
 
-    Ok(json!({ name: pile }))
+        caller.can_see(&game, &name)?;
+        let pile = game.pile_view(&name, caller.player())?;
+        Ok(Tagged(game.version(), json!({ name: pile })))
+    })
 }
 
-use rocket_contrib::json::Json;
+#[get("/game/<id>/<name>/score")]
+pub fn get_pile_score(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let game: Game<C> = Game::load(&conn, id)?;
+        caller.can_see(&game, &name)?;
+        let score = game.score(&name, caller.player())?;
+        Ok(Tagged(
+            game.version(),
+            json!({ "pile": name, "score": score }),
+        ))
+    })
+}
 
 #[put("/game/<id>/<name>", data = "<drawdata>")]
 pub fn draw_from_pile(
     conn: GamesDbConn,
     id: String,
     name: String,
-    drawdata: Json<DrawData>,
-) -> Result<JsonValue, CardAPIError> {
-    let mut game = Game::load(&conn, id)?;
-    game.draw(&drawdata.source, &name, &drawdata.selection)?;
-    game.save(&conn);
-    Ok(game.into())
+    drawdata: Result<Json<serde_json::Value>, JsonError>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let drawdata = required(drawdata)?;
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let drawdata: DrawData<C> = parse(drawdata)?;
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_move(&game, &drawdata.source)?;
+        game.draw(
+            &drawdata.source,
+            &name,
+            &drawdata.selection,
+            &drawdata.placement,
+            caller.player(),
+        )?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/<name>/shuffle", data = "<method>")]
+pub fn shuffle_pile(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    method: Result<Json<ShuffleMethod>, JsonError>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let method = optional(method)?.unwrap_or(ShuffleMethod::Wash);
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_move(&game, &name)?;
+        game.shuffle(&name, &method)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/<name>/flip", data = "<selection>")]
+pub fn flip_cards(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    selection: Result<Json<serde_json::Value>, JsonError>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let selection = required(selection)?;
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let selection: CardSelection<C> = parse(selection)?;
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_move(&game, &name)?;
+        game.flip(&name, &selection, caller.player())?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[put("/game/<id>/<name>/face", data = "<facedata>")]
+pub fn turn_pile(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    facedata: Json<FaceData>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_turn(&game, &name, facedata.face)?;
+        game.turn(&name, facedata.face)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[put("/game/<id>/<name>/rules", data = "<rules>")]
+pub fn set_pile_rules(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    rules: Result<Json<serde_json::Value>, JsonError>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let rules = required(rules)?;
+    let caller = bearer.caller(&conn, &id)?;
+    caller.require_host()?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let rules: PileRules<C> = parse(rules)?;
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        game.set_pile_rules(&name, rules)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/<name>/reverse")]
+pub fn reverse_pile(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_move(&game, &name)?;
+        game.reorder(&name, &PileOrder::Reverse)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/<name>/sort", data = "<sortdata>")]
+pub fn sort_pile(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    sortdata: Result<Json<SortData>, JsonError>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let by = optional(sortdata)?.unwrap_or_default().by;
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_move(&game, &name)?;
+        game.reorder(&name, &PileOrder::Sort { by })?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/reset")]
+pub fn reset_game(
+    conn: GamesDbConn,
+    id: String,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.require_host()?;
+        game.reset()?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/return", data = "<piles>")]
+pub fn return_piles(
+    conn: GamesDbConn,
+    id: String,
+    piles: Json<Vec<String>>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        for name in piles.iter() {
+            caller.can_move(&game, name)?;
+        }
+        game.return_to_deck(&piles)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/moves", data = "<moves>")]
+pub fn apply_moves(
+    conn: GamesDbConn,
+    id: String,
+    moves: Result<Json<serde_json::Value>, JsonError>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let moves = required(moves)?;
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let moves: Vec<Move<C>> = parse(moves)?;
+        let game = conn.transaction::<_, CardAPIError, _>(|| {
+            let mut game: Game<C> = Game::load(&conn, id)?;
+            game.expect_version(&if_match)?;
+            for (index, m) in moves.iter().enumerate() {
+                caller
+                    .can_move(&game, &m.from)
+                    .map_err(|e| CardAPIError::MoveFailed(index, Box::new(e)))?;
+            }
+            game.apply(&moves, caller.player())?;
+            game.try_save(&conn)?;
+            Ok(game)
+        })?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/end-turn", data = "<turndata>")]
+pub fn end_turn(
+    conn: GamesDbConn,
+    id: String,
+    turndata: Result<Json<TurnData>, JsonError>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let turndata = optional(turndata)?.unwrap_or_default();
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_end_turn(&game)?;
+        game.end_turn(turndata.skip, turndata.reverse)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/undo")]
+pub fn undo_move(
+    conn: GamesDbConn,
+    id: String,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        let history = load_events(&conn, game.id())?;
+        caller.can_move_again(&game, next_undo(&history).map(|(_, event)| event))?;
+        game.undo(&history)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/redo")]
+pub fn redo_move(
+    conn: GamesDbConn,
+    id: String,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        let history = load_events(&conn, game.id())?;
+        caller.can_move_again(&game, next_redo(&history).map(|(_, event)| event))?;
+        game.redo(&history)?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[post("/game/<id>/reveal")]
+pub fn reveal_seed(
+    conn: GamesDbConn,
+    id: String,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.require_host()?;
+        game.reveal()?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[get("/game/<id>/verify")]
+pub fn verify_seed(conn: GamesDbConn, id: String) -> Result<JsonValue, CardAPIError> {
+    with_cards!(card_type(&conn, &id)?, C => {
+        let game: Game<C> = Game::load(&conn, id)?;
+        let verification = game.verify(&load_events(&conn, game.id())?)?;
+        Ok(json!(verification))
+    })
+}
+
+#[get("/game/<id>/<name>/draw?<to>&<selection..>")]
+pub fn draw_to_pile(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    to: String,
+    selection: Result<SelectionQuery, String>,
+    if_match: IfMatch,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let selection = selection.map_err(CardAPIError::InvalidSelection)?;
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let selection: CardSelection<C> = selection
+            .selection()
+            .map_err(CardAPIError::InvalidSelection)?;
+        let mut game: Game<C> = Game::load(&conn, id)?;
+        game.expect_version(&if_match)?;
+        caller.can_move(&game, &name)?;
+        game.draw(&name, &to, &selection, &Placement::Top, caller.player())?;
+        game.try_save(&conn)?;
+        Ok(Tagged(game.version(), game.view(&caller)))
+    })
+}
+
+#[get("/game/<id>/<name>/peek?<selection..>")]
+pub fn peek_pile(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    selection: Result<SelectionQuery, String>,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let selection = selection.map_err(CardAPIError::InvalidSelection)?;
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let selection: CardSelection<C> = selection
+            .selection()
+            .map_err(CardAPIError::InvalidSelection)?;
+        let game: Game<C> = Game::load(&conn, id)?;
+        caller.can_see(&game, &name)?;
+        let cards = game.peek(&name, &selection, caller.player())?;
+        Ok(Tagged(game.version(), json!({ name: cards })))
+    })
+}
+
+#[post("/game/<id>/<name>/peek", data = "<selection>")]
+pub fn peek_pile_selection(
+    conn: GamesDbConn,
+    id: String,
+    name: String,
+    selection: Result<Json<serde_json::Value>, JsonError>,
+    bearer: Bearer,
+) -> Result<Tagged<JsonValue>, CardAPIError> {
+    let selection = required(selection)?;
+    let caller = bearer.caller(&conn, &id)?;
+    with_cards!(card_type(&conn, &id)?, C => {
+        let selection: CardSelection<C> = parse(selection)?;
+        let game: Game<C> = Game::load(&conn, id)?;
+        caller.can_see(&game, &name)?;
+        let cards = game.peek(&name, &selection, caller.player())?;
+        Ok(Tagged(game.version(), json!({ name: cards })))
+    })
 }
@@ -0,0 +1,76 @@
+use super::cards::*;
+use super::pile::PileRules;
+
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+// Misspelled fields are an error rather than falling back to their defaults
+#[serde(default, deny_unknown_fields, bound = "")]
+pub struct RuleSet<C: PlayingCard = Card> {
+    // The kind of card the game is played with, which the other fields are in terms of
+    pub cards: CardType,
+    // How cards rank and score
+    pub valuation: C::Ranking,
+    pub player_count: usize,
+    // Which cards make up a single deck
+    pub definition: C::Definition,
+    // Number of full decks shuffled together, e.g 6 for a blackjack shoe
+    pub decks: usize,
+    // Number of jokers added to the deck, up to the card type's, e.g 2 for French cards
+    pub jokers: usize,
+    pub deck: CardSelection<C>,
+    pub piles: Vec<String>,
+    // Piles each player gets for themselves on joining, e.g "hand" becomes "hand-0" for seat 0
+    pub private_piles: Vec<String>,
+    // Piles whose cards lie face down, e.g "deck"
+    pub face_down: Vec<String>,
+    // Piles any player can take from when it isn't their turn, e.g a snap pile
+    pub anytime_piles: Vec<String>,
+    // Rules for piles by name, private piles use the name they're given here e.g "hand"
+    pub pile_rules: HashMap<String, PileRules<C>>,
+}
+
+impl<C: PlayingCard> Default for RuleSet<C> {
+    fn default() -> Self {
+        RuleSet {
+            cards: C::TYPE,
+            valuation: C::Ranking::default(),
+            player_count: 1,
+            definition: C::Definition::default(),
+            decks: 1,
+            jokers: 0,
+            deck: CardSelection::All(true),
+            piles: Vec::new(),
+            private_piles: Vec::new(),
+            face_down: Vec::new(),
+            anytime_piles: Vec::new(),
+            pile_rules: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn misspelled_fields_are_rejected() {
+        assert!(serde_json::from_str::<RuleSet>(r#"{ "player_cout": 4 }"#).is_err());
+        assert!(serde_json::from_str::<RuleSet>(r#"{ "valuation": { "ace_hi": true } }"#).is_err());
+        // Valuation fields only go under "valuation"
+        assert!(serde_json::from_str::<RuleSet>(r#"{ "ace_high": true }"#).is_err());
+    }
+
+    #[test]
+    fn missing_fields_use_their_defaults() {
+        let rules: RuleSet =
+            serde_json::from_str(r#"{ "player_count": 4, "valuation": { "ace_high": true } }"#)
+                .unwrap();
+        assert_eq!(rules.player_count, 4);
+        assert!(rules.valuation.ace_high);
+        assert_eq!(rules.valuation.pictures, PictureValues::Face);
+        assert_eq!(rules.decks, 1);
+    }
+}
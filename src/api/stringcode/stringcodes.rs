@@ -40,6 +40,10 @@ impl<T: HasStringCode + Eq> StringCodes<T> {
     pub fn len(&self) -> usize {
         self._inner.len()
     }
+
+    pub fn iter(&self) -> std::slice::Iter<T> {
+        self._inner.iter()
+    }
 }
 
 impl<T: HasStringCode + Serialize> Serialize for StringCodes<T> {
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    // Up through the seat numbers
+    Clockwise,
+    Counterclockwise,
+}
+
+// Whose go it is, by seat
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Turn {
+    pub seat: usize,
+    pub direction: Direction,
+    // How many turns have ended so far
+    pub number: u32,
+}
+
+impl Default for Turn {
+    fn default() -> Self {
+        Turn {
+            seat: 0,
+            direction: Direction::Clockwise,
+            number: 0,
+        }
+    }
+}
+
+impl Turn {
+    // Passes play on to the next taken seat, skipping over `skip` players. Reversing
+    // happens first, so the turn goes back the way it came
+    pub fn end(&mut self, seats: &Vec<usize>, skip: usize, reverse: bool) {
+        if reverse {
+            self.direction = match self.direction {
+                Direction::Clockwise => Direction::Counterclockwise,
+                Direction::Counterclockwise => Direction::Clockwise,
+            };
+        }
+        let mut seats = seats.clone();
+        seats.sort();
+        // Going all the way round the table lands back on the same seat
+        let skip = skip % seats.len().max(1);
+        for _ in 0..=skip {
+            self.seat = self.next(&seats);
+        }
+        self.number += 1;
+    }
+
+    // The next seat in `seats`, which are sorted
+    fn next(&self, seats: &Vec<usize>) -> usize {
+        let next = match self.direction {
+            Direction::Clockwise => seats.iter().find(|s| **s > self.seat).or(seats.first()),
+            Direction::Counterclockwise => seats
+                .iter()
+                .rev()
+                .find(|s| **s < self.seat)
+                .or(seats.last()),
+        };
+        next.cloned().unwrap_or(self.seat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ended(seats: &[usize], times: usize, skip: usize, reverse: bool) -> Turn {
+        let mut turn = Turn::default();
+        for _ in 0..times {
+            turn.end(&seats.to_vec(), skip, reverse);
+        }
+        turn
+    }
+
+    #[test]
+    fn passes_to_the_next_taken_seat_and_wraps() {
+        assert_eq!(ended(&[0, 2, 5], 1, 0, false).seat, 2);
+        assert_eq!(ended(&[0, 2, 5], 2, 0, false).seat, 5);
+        let turn = ended(&[0, 2, 5], 3, 0, false);
+        assert_eq!(turn.seat, 0);
+        assert_eq!(turn.number, 3);
+    }
+
+    #[test]
+    fn skip_passes_over_players() {
+        assert_eq!(ended(&[0, 1, 2, 3], 1, 1, false).seat, 2);
+        assert_eq!(ended(&[0, 1, 2, 3], 1, 3, false).seat, 0);
+        // Whole times round the table make no difference
+        assert_eq!(ended(&[0, 1, 2], 1, 4, false).seat, 2);
+        assert_eq!(ended(&[0, 1, 2], 1, usize::max_value(), false).seat, 1);
+    }
+
+    #[test]
+    fn reverse_turns_back_before_moving_on() {
+        let turn = ended(&[0, 1, 2, 3], 1, 0, true);
+        assert_eq!(turn.direction, Direction::Counterclockwise);
+        assert_eq!(turn.seat, 3);
+
+        let mut turn = turn;
+        turn.end(&vec![0, 1, 2, 3], 1, false);
+        assert_eq!(turn.seat, 1);
+        turn.end(&vec![0, 1, 2, 3], 0, true);
+        assert_eq!(turn.direction, Direction::Clockwise);
+        assert_eq!(turn.seat, 2);
+    }
+
+    #[test]
+    fn no_seats_leaves_the_turn_where_it_is() {
+        let turn = ended(&[], 2, 5, false);
+        assert_eq!(turn.seat, 0);
+        assert_eq!(turn.number, 2);
+    }
+}
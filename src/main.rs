@@ -28,9 +28,34 @@ fn main() {
             "/",
             routes![
                 api::get_game,
+                api::get_game_at,
+                api::join_game,
+                api::give_seed,
+                api::find_card,
+                api::get_events,
+                api::replay_game,
+                api::get_history,
                 api::get_pile,
+                api::get_pile_score,
                 api::new_game,
                 api::draw_from_pile,
+                api::draw_to_pile,
+                api::shuffle_pile,
+                api::flip_cards,
+                api::turn_pile,
+                api::set_pile_rules,
+                api::reverse_pile,
+                api::sort_pile,
+                api::reset_game,
+                api::return_piles,
+                api::apply_moves,
+                api::end_turn,
+                api::undo_move,
+                api::redo_move,
+                api::reveal_seed,
+                api::verify_seed,
+                api::peek_pile,
+                api::peek_pile_selection,
             ],
         )
         .launch();
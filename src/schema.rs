@@ -2,5 +2,54 @@ table! {
     games (id) {
         id -> Text,
         json -> Text,
+        version -> Integer,
     }
 }
+
+table! {
+    piles (game_id, name) {
+        game_id -> Text,
+        name -> Text,
+        rules -> Nullable<Text>,
+    }
+}
+
+table! {
+    pile_cards (game_id, pile, position) {
+        game_id -> Text,
+        pile -> Text,
+        position -> Integer,
+        code -> Text,
+    }
+}
+
+table! {
+    game_events (game_id, seq) {
+        game_id -> Text,
+        seq -> Integer,
+        kind -> Text,
+        from_pile -> Nullable<Text>,
+        to_pile -> Nullable<Text>,
+        selection -> Nullable<Text>,
+        cards -> Text,
+        created_at -> Timestamp,
+        from_positions -> Nullable<Text>,
+        to_positions -> Nullable<Text>,
+        target -> Nullable<Integer>,
+        turned -> Nullable<Text>,
+    }
+}
+
+table! {
+    tokens (hash) {
+        hash -> Text,
+        game_id -> Text,
+        player_id -> Nullable<Text>,
+    }
+}
+
+joinable!(piles -> games (game_id));
+joinable!(game_events -> games (game_id));
+joinable!(tokens -> games (game_id));
+
+allow_tables_to_appear_in_same_query!(games, piles, pile_cards, game_events, tokens);